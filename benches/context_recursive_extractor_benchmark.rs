@@ -1,4 +1,3 @@
-use anyhow::Result;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use ego_tree::NodeRef;
 use rsoup::extractors::context_v1::ContextExtractor;
@@ -9,7 +8,7 @@ use std::{fs, path::Path};
 fn get_doc(filename: &str) -> Html {
     let html_file = Path::new(env!("CARGO_MANIFEST_DIR"))
         .join("tests/resources")
-        .join("wikipedia/List_of_highest_mountains_on_Earth.html");
+        .join(filename);
     let html = fs::read_to_string(html_file).unwrap();
     Html::parse_document(&html)
 }
@@ -50,12 +49,7 @@ fn extract_context_recur(extractor: &ContextExtractor, table_el: NodeRef<Node>)
 fn criterion_benchmark(c: &mut Criterion) {
     let extractor = ContextExtractor::default();
     let filename = "wikipedia/List_of_highest_mountains_on_Earth.html";
-
-    let html_file = Path::new(env!("CARGO_MANIFEST_DIR"))
-        .join("tests/resources")
-        .join(filename);
-    let html = fs::read_to_string(html_file).unwrap();
-    let doc = Html::parse_document(&html);
+    let doc = get_doc(filename);
 
     let selector = Selector::parse("table").unwrap();
     let elements = doc.select(&selector).collect::<Vec<_>>();
@@ -1,15 +1,11 @@
-use anyhow::Result;
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use ego_tree::NodeRef;
-use rsoup::extractors::context_v1::ContextExtractor;
-use rsoup::models::rich_text::RichText;
-use scraper::{ElementRef, Html, Node, Selector};
+use criterion::{criterion_group, criterion_main, Criterion};
+use scraper::{ElementRef, Html, Selector};
 use std::{fs, path::Path};
 
 fn get_doc(filename: &str) -> Html {
     let html_file = Path::new(env!("CARGO_MANIFEST_DIR"))
         .join("tests/resources")
-        .join("wikipedia/List_of_highest_mountains_on_Earth.html");
+        .join(filename);
     let html = fs::read_to_string(html_file).unwrap();
     Html::parse_document(&html)
 }
@@ -31,14 +27,8 @@ fn get_text_v2(elements: &[ElementRef]) -> usize {
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let extractor = ContextExtractor::default();
     let filename = "wikipedia/List_of_highest_mountains_on_Earth.html";
-
-    let html_file = Path::new(env!("CARGO_MANIFEST_DIR"))
-        .join("tests/resources")
-        .join(filename);
-    let html = fs::read_to_string(html_file).unwrap();
-    let doc = Html::parse_document(&html);
+    let doc = get_doc(filename);
 
     let query = "td";
     let selector = Selector::parse(query).unwrap();
@@ -1,6 +1,29 @@
 from __future__ import annotations
 
-from typing import Iterator, Optional
+from typing import TYPE_CHECKING, Callable, Iterator, Literal, Optional, Union, overload
+
+if TYPE_CHECKING:
+    import pyarrow
+
+class OverlapSpanPyError(Exception):
+    """Raised when a cell's colspan overlaps with a cell spanning from the rows above"""
+
+    table_id: str
+    # index of the offending cell in `Table.rows` and `Row.cells`
+    row: int
+    col: int
+    rowspan: int
+    colspan: int
+
+class InvalidCellSpanPyError(Exception):
+    """Raised when a cell that is not the last cell of its row spans beyond the number of columns"""
+
+    table_id: str
+    # index of the offending cell in `Table.rows` and `Row.cells`
+    row: int
+    col: int
+    rowspan: int
+    colspan: int
 
 class Document:
     def __init__(self, url: str, doc: str): ...
@@ -25,7 +48,15 @@ class TableExtractor:
         discard_tags: Optional[list[str]] = None,
         only_keep_inline_tags: bool = True,
         html_error_forgiveness: bool = True,
-    ) -> None: ...
+        extract_nested_tables: bool = False,
+        span_mode: Literal["default", "browser"] = "default",
+    ) -> None:
+        """
+        Arguments:
+            extract_nested_tables: also extract tables that contain other tables, the nested tables are attached to the cells containing them (`Cell.nested_tables`) and only the outermost tables are returned
+            span_mode: algorithm used to span tables, see `Table.span`
+        """
+        ...
     def extract(
         self,
         url: str,
@@ -34,11 +65,52 @@ class TableExtractor:
         auto_pad: bool = True,
         extract_context: bool = True,
     ) -> list[Table]: ...
+    def extract_with_report(
+        self,
+        url: str,
+        doc: str,
+        auto_span: bool = True,
+        auto_pad: bool = True,
+        extract_context: bool = True,
+    ) -> tuple[list[Table], ExtractionReport]: ...
+
+class ExtractionReport:
+    """Report of a table extraction, containing one entry per <table> element in document order"""
+
+    @property
+    def tables(self) -> list[TableReport]: ...
+    def n_extracted(self) -> int: ...
+    def n_skipped(self) -> int: ...
+
+class TableReport:
+    @property
+    def index(self) -> int: ...
+    @property
+    def table_id(self) -> Optional[str]: ...
+    @property
+    def skip_reason(
+        self,
+    ) -> Optional[
+        Literal["nested_table", "no_cells", "span_error", "parent_skipped"]
+    ]:
+        """None if the table is extracted"""
+        ...
+    @property
+    def errors(self) -> list[str]: ...
 
 class Table:
+    """A table extracted from a webpage.
+
+    Rows (and their cells) and context obtained from a table are views of the table: modifying them modifies the table. Other values such as `attrs`, `columns`, `Cell.value` or `Cell.nested_tables` are returned as copies, assign them (e.g., `cell.value = text`) to modify the table. Rows, cells and context given to the constructors are copied.
+    """
+
     id: str
     url: str
     caption: str
+    # header rows and columns, only available after detect_headers()
+    header: Optional[TableHeader]
+    # id of the table containing this table if it is nested in a cell of another table
+    parent_id: Optional[str]
 
     def __init__(
         self,
@@ -48,15 +120,108 @@ class Table:
         attrs: dict[str, str],
         context: list[ContentHierarchy],
         rows: list[Row],
+        parent_id: Optional[str] = None,
+        columns: list[Column] = [],
+        header: Optional[TableHeader] = None,
     ) -> None: ...
     @property
     def attrs(self) -> dict[str, str]: ...
     @property
+    def columns(self) -> list[Column]:
+        """Column descriptors from <colgroup> and <col> elements, one per logical column after span()"""
+        ...
+    @property
     def context(self) -> list[ContentHierarchy]: ...
     @property
     def rows(self) -> list[Row]: ...
-    def span(self) -> Table: ...
+    def span(self, mode: Literal["default", "browser"] = "default") -> Table:
+        """Span the table by copying values to merged cells.
+
+        Arguments:
+            mode: "default" raises OverlapSpanPyError or InvalidCellSpanPyError on tables it cannot handle, "browser" follows the WHATWG "forming a table" algorithm and always produces a grid
+        """
+        ...
     def pad(self) -> Optional[Table]: ...
+    def detect_headers(self, locale: str = "en") -> TableHeader:
+        """Detect the header rows and columns of the table (expected to be spanned) and store them in `header`.
+
+        Header rows are detected from th, thead, scope="col", bold/strong-only content and text cells on top of numeric columns. Header columns are detected from th, scope="row" and bold/strong-only content.
+
+        Arguments:
+            locale: locale of the numbers of the cells, see `parse_values`
+        """
+        ...
+    @overload
+    def flatten_headers(
+        self, n_header_rows: Optional[int] = None, separator: str = " / "
+    ) -> tuple[list[str], list[list[str]]]: ...
+    @overload
+    def flatten_headers(
+        self, n_header_rows: Optional[int], separator: None
+    ) -> tuple[list[list[str]], list[list[str]]]:
+        """Flatten the header rows of the table (expected to be spanned) into one name per column.
+
+        Returns the column names and the texts of the remaining body rows. Column names are the texts of the stacked header cells joined with the separator, or the list of these texts (hierarchical path) when separator is None. If n_header_rows is None, `header` is used or detected.
+        """
+        ...
+    def to_csv(
+        self, format: Literal["text", "html", "markdown"] = "text", header: bool = True
+    ) -> str:
+        """Write the table as CSV (RFC 4180: CRLF line endings, fields with commas, quotes or newlines are quoted).
+
+        Arguments:
+            format: how cells are rendered: their text, their inner HTML, or their text rendered as markdown (see `RichText.to_markdown`)
+            header: whether to write the leading rows made of header cells (`is_header`)
+        """
+        ...
+    def to_tsv(
+        self, format: Literal["text", "html", "markdown"] = "text", header: bool = True
+    ) -> str:
+        """Write the table as TSV, same as `to_csv`."""
+        ...
+    def to_markdown(
+        self,
+        format: Literal["text", "html", "markdown"] = "text",
+        max_col_width: Optional[int] = None,
+    ) -> str:
+        """Write the table as a GitHub-flavored markdown table (`|` escaped, line breaks as <br>).
+
+        The header rows (`header`, or the detected ones) are flattened into the header of the markdown table, stacked header cells are joined with " / ".
+
+        Arguments:
+            format: how cells are rendered, "markdown" renders the cells as markdown, with line breaks as <br>
+            max_col_width: cells wider than this are truncated with an ellipsis
+        """
+        ...
+    def to_text_grid(
+        self,
+        format: Literal["text", "html", "markdown"] = "text",
+        max_col_width: Optional[int] = None,
+    ) -> str:
+        """Write the table as an aligned plain-text grid, same options as `to_markdown`."""
+        ...
+    def parse_values(self, locale: str = "en") -> list[list[TypedValue]]:
+        """Parse the text of every cell into a typed value, in the order of the rows and cells.
+
+        Arguments:
+            locale: locale of the decimal and thousands separators and of the numeric dates, e.g., "en", "en-GB", "de" or "fr"
+        """
+        ...
+    def column_types(
+        self, locale: str = "en"
+    ) -> list[Literal["empty", "number", "percentage", "quantity", "date", "text"]]:
+        """Infer the dominant type of each column (the most frequent type of its non-empty cells) from the cells below the header rows.
+
+        The header rows are the stored ones (`header`), or they are detected if the table has none.
+        """
+        ...
+    def to_html(self, grid: bool = False) -> str:
+        """Write the table as HTML with thead/tbody/tfoot, caption, columns and attributes.
+
+        Arguments:
+            grid: if False, merged cells (including the copies made by `span`) are written once with rowspan/colspan, otherwise every cell of the grid is written
+        """
+        ...
     def n_rows(self) -> int: ...
     def shape(self) -> tuple[int, int]: ...
     def get_row(self, ri: int) -> Row: ...
@@ -64,9 +229,16 @@ class Table:
     def iter_cells(self) -> Iterator[Cell]: ...
     def enumerate_cells(self) -> Iterator[tuple[int, int, Cell]]: ...
     def iter_rows(self) -> Iterator[Row]: ...
-    def to_bytes(self) -> bytes: ...
+    def to_bytes(self) -> bytes:
+        """Encode the table in the versioned binary format (also used by pickle)."""
+        ...
     @staticmethod
-    def from_bytes(dat: bytes) -> Table: ...
+    def from_bytes(dat: bytes) -> Table:
+        """Decode a table from the versioned binary format. Tables written by older versions are migrated.
+
+        Raises ValueError if the data is not a table or is written by a newer version of rsoup.
+        """
+        ...
     def to_json(self) -> str: ...
     @staticmethod
     def from_json(dat: str) -> Table: ...
@@ -76,8 +248,93 @@ class Table:
     def to_dict(self) -> dict: ...
     def to_list(self) -> list[list[str]]: ...
 
+class TableWriter:
+    """Write tables one by one to a file: newline-delimited JSON ("jsonl") or postcard records prefixed by their length as a little-endian u64 ("postcard").
+
+    Use it as a context manager or call `close` at the end.
+    """
+
+    def __init__(
+        self, path: str, format: Literal["jsonl", "postcard"] = "jsonl"
+    ) -> None: ...
+    def write(self, table: Table) -> None: ...
+    def write_many(self, tables: list[Table]) -> None: ...
+    def flush(self) -> None: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> TableWriter: ...
+    def __exit__(self, exc_type, exc_value, traceback) -> None: ...
+
+class TableReader:
+    """Iterate over the tables of a file written by `TableWriter`, reading one table at a time."""
+
+    def __init__(
+        self, path: str, format: Literal["jsonl", "postcard"] = "jsonl"
+    ) -> None: ...
+    def __iter__(self) -> Iterator[Table]: ...
+    def __next__(self) -> Table: ...
+
+class TableHeader:
+    def __init__(self, n_rows: int, n_cols: int) -> None: ...
+    @property
+    def n_rows(self) -> int:
+        """Number of header rows at the top of the table"""
+        ...
+    @property
+    def n_cols(self) -> int:
+        """Number of header columns on the left of the table"""
+        ...
+
+class TypedValue:
+    """Value parsed from the text of a cell, after removing footnote markers such as "[a]"."""
+
+    @property
+    def value_type(
+        self,
+    ) -> Literal["empty", "number", "percentage", "quantity", "date", "text"]: ...
+    @property
+    def number(self) -> Optional[float]:
+        """Value of numbers, percentages (12.0 for "12 %") and quantities"""
+        ...
+    @property
+    def unit(self) -> Optional[str]:
+        """Unit of quantities (e.g., "m" or "$") and "%" for percentages. Quantities have a currency or a common unit of measurement, a number followed by any word (e.g., "3 wins") is text."""
+        ...
+    @property
+    def date(self) -> Optional[tuple[int, int, Optional[int]]]:
+        """Year, month and day of dates, the day is None if the text has no day (e.g., "March 2016")"""
+        ...
+    @property
+    def text(self) -> str:
+        """Text of the cell without footnote markers and with collapsed whitespaces"""
+        ...
+    @property
+    def footnotes(self) -> list[str]:
+        """Content of the removed footnote markers (e.g., "a" for "[a]")"""
+        ...
+
+class Column:
+    span: int
+
+    def __init__(self, span: int = 1, attrs: dict[str, str] = {}) -> None: ...
+    @property
+    def attrs(self) -> dict[str, str]: ...
+
 class Row:
-    def __init__(self, cells: list[Cell], attrs: dict[str, str]) -> None: ...
+    """A row of a table, a view of the row when it is obtained from a table (see `Table`)."""
+
+    # row group that the row belongs to
+    section: Literal["thead", "tbody", "tfoot"]
+    # index of the row group in the table, consecutive rows of the same section can belong
+    # to different groups
+    group: int
+
+    def __init__(
+        self,
+        cells: list[Cell],
+        attrs: dict[str, str],
+        section: Literal["thead", "tbody", "tfoot"] = "tbody",
+        group: int = 0,
+    ) -> None: ...
     @property
     def cells(self) -> list[Cell]: ...
     @property
@@ -85,30 +342,93 @@ class Row:
     def get_cell(self, ci: int) -> Cell: ...
     def iter_cells(self) -> Iterator[Cell]: ...
     def to_dict(self) -> dict: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(dat: str) -> Row: ...
+    def to_bytes(self) -> bytes:
+        """Encode in the versioned binary format."""
+        ...
+    @staticmethod
+    def from_bytes(dat: bytes) -> Row: ...
     def to_list(self) -> list[str]: ...
 
 class Cell:
+    """A cell of a table, a view of the cell when it is obtained from a row (see `Table`)."""
+
     is_header: bool
     rowspan: int
     colspan: int
-    value: RichText
 
+    @property
+    def value(self) -> FrozenRichText:
+        """Read-only copy of the text of the cell, modify a copy of it and assign it back to update the cell"""
+        ...
+    @value.setter
+    def value(self, value: RichText) -> None: ...
     def __init__(
-        self, is_header: bool, rowspan: int, colspan: int, value: RichText
+        self,
+        is_header: bool,
+        rowspan: int,
+        colspan: int,
+        attrs: dict[str, str],
+        value: RichText,
+        nested_tables: list[Table] = [],
     ) -> None: ...
     @property
     def attrs(self) -> dict[str, str]: ...
+    @property
+    def nested_tables(self) -> list[Table]:
+        """Tables inside the cell, only the top-left cell of a merged cell has them after span()"""
+        ...
+    # whether the cell is a copy of a merged cell made by span(), i.e., not at the top-left position of the merged cell
+    is_span_copy: bool
+    @property
+    def origin_row(self) -> Optional[int]:
+        """Row of the merged cell that this cell comes from, only available after span()"""
+        ...
+    @property
+    def origin_col(self) -> Optional[int]:
+        """Column of the merged cell that this cell comes from, only available after span()"""
+        ...
+    @property
+    def original_rowspan(self) -> Optional[int]: ...
+    @property
+    def original_colspan(self) -> Optional[int]: ...
     def to_dict(self) -> dict: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(dat: str) -> Cell: ...
+    def to_bytes(self) -> bytes:
+        """Encode in the versioned binary format."""
+        ...
+    @staticmethod
+    def from_bytes(dat: bytes) -> Cell: ...
+    def parse_value(self, locale: str = "en") -> TypedValue:
+        """Parse the text of the cell into a typed value, see `Table.parse_values`."""
+        ...
 
 class ContentHierarchy:
+    """Content leading to a table, a view of the context of the table when it is obtained from `Table.context`. Its texts are returned as read-only copies."""
+
     level: int
-    heading: RichText
 
     @property
-    def content_before(self) -> list[RichText]: ...
+    def heading(self) -> FrozenRichText: ...
+    @heading.setter
+    def heading(self, heading: RichText) -> None: ...
+    @property
+    def content_before(self) -> list[FrozenRichText]: ...
     @property
-    def content_after(self) -> list[RichText]: ...
+    def content_after(self) -> list[FrozenRichText]: ...
     def to_dict(self) -> dict: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(dat: str) -> ContentHierarchy: ...
+    def to_bytes(self) -> bytes:
+        """Encode in the versioned binary format."""
+        ...
+    @staticmethod
+    def from_bytes(dat: bytes) -> ContentHierarchy: ...
     @staticmethod
     def from_dict(c: dict) -> ContentHierarchy: ...
 
@@ -143,23 +463,131 @@ class RichTextConfig:
         ...
 
 class RichText:
+    """Text with the tree of HTML elements creating it.
+
+    Offsets given to and returned by the methods are indices of the Python string `text` (code points). The offsets of elements stored by `to_dict`, `to_json` and `to_bytes` are in bytes of the UTF-8 text, use `get_element_by_id` to get them in code points.
+    """
+
     @staticmethod
     def from_str(text: str) -> RichText: ...
     @property
     def text(self) -> str: ...
-    def len(self) -> int: ...
+    def len(self) -> int:
+        """Length of the text in code points, same as `len(text)`."""
+        ...
+    def byte_len(self) -> int:
+        """Length of the text in bytes of its UTF-8 encoding."""
+        ...
     def iter_element_id(self) -> Iterator[int]: ...
     def iter_element_id_preorder(self) -> Iterator[int]: ...
     def get_element_tag_by_id(self, id: int) -> str: ...
     def get_element_attr_by_id(self, id: int, attr: str) -> Optional[str]: ...
     def set_element_attr_by_id(self, id: int, attr: str, value: str) -> None: ...
-    def get_element_by_id(self, id: int) -> RichTextElement: ...
-    def set_element_by_id(self, id: int, RichTextElement) -> None: ...
-    def to_dict(self) -> dict: ...
+    def get_element_by_id(
+        self, id: int, unit: Literal["codepoint", "utf16", "byte"] = "codepoint"
+    ) -> RichTextElement:
+        """Get a copy of an element with its offsets in the unit: code points (indices of Python strings), UTF-16 code units (indices of JavaScript strings) or bytes."""
+        ...
+    def set_element_by_id(
+        self,
+        id: int,
+        element: RichTextElement,
+        unit: Literal["codepoint", "utf16", "byte"] = "codepoint",
+    ) -> None:
+        """Replace an element, its offsets are in the unit."""
+        ...
+    def convert_offset(
+        self,
+        offset: int,
+        from_unit: Literal["codepoint", "utf16", "byte"] = "codepoint",
+        to_unit: Literal["codepoint", "utf16", "byte"] = "utf16",
+    ) -> int:
+        """Convert an offset of the text from one unit to another. Raises IndexError if the offset is out of the text or in the middle of a character."""
+        ...
+    def to_html(
+        self,
+        *,
+        render_outer_element: bool = True,
+        render_element_attrs: bool = False,
+        allowed_attrs: Optional[list[str]] = None,
+    ) -> str:
+        """Render the text as HTML. Text and attribute values are escaped, attributes are written in alphabetical order, and void elements (e.g., `br`) have no end tag.
+
+        Arguments:
+            render_outer_element: whether to render the outer element of the text
+            render_element_attrs: whether to render the attributes of the elements
+            allowed_attrs: if given, only these attributes are rendered (regardless of `render_element_attrs`)
+        """
+        ...
+    def to_markdown(self) -> str:
+        """Render the text as markdown: links as `[text](href)`, `b`/`strong` as `**text**`, `i`/`em` as `_text_`, `code` as code spans, and `sup`/`sub` as inline HTML.
+
+        Line breaks of the text, `br` and block elements are written as hard line breaks (a backslash followed by a newline). Markdown markup characters of the text are escaped.
+        """
+        ...
+    def to_text_with_links(self) -> str:
+        """Render the text as plain text with a footnote marker `[n]` after each link, and the hrefs listed as `[n]: href` at the end. Links to the same href share the same footnote."""
+        ...
+    def select(self, query: str) -> list[int]:
+        """Ids of the elements matching a CSS selector (e.g., "a[href]"), in preorder. The outer element is included if it matches.
+
+        Supported selectors: tags, `*`, classes, ids, attributes (`[href]`, `[href="..."]`, `~=`, `^=`, `$=`, `*=`), descendant and child combinators, and lists separated by commas.
+        """
+        ...
+    def elements_at(self, offset: int) -> list[int]:
+        """Ids of the elements whose text contains the character at the offset (in code points), from the outer element to the innermost one."""
+        ...
+    def slice(self, start: int, end: int) -> RichText:
+        """Part of the text in the range, with the elements overlapping the range clipped to it.
+
+        Empty elements (e.g., `br`) are kept if they are at the start of or inside the range, or at the end of the text. Raises IndexError if the range is invalid.
+        """
+        ...
+    def concat(self, other: RichText) -> RichText:
+        """Concatenate two rich texts. The outer element of the result is a pseudo-element (empty tag) containing the elements of both texts."""
+        ...
+    def replace(self, start: int, end: int, value: str) -> RichText:
+        """Replace the text in the range with a string.
+
+        Elements containing the range contain the new string, elements inside the range are removed, and elements partially overlapping the range are clipped to the remaining text.
+        """
+        ...
+    def insert(self, offset: int, value: str) -> RichText:
+        """Insert a string at the offset, it belongs to the elements strictly containing the offset."""
+        ...
+    def remove_elements(
+        self, predicate: Union[str, Callable[[RichTextElement], bool]]
+    ) -> RichText:
+        """Remove the elements matching a CSS selector or a function (receiving elements with offsets in code points), together with their text and descendants, and shift the offsets of the remaining elements. The outer element is never removed.
+
+        See `select` for the supported selectors, e.g., "sup.reference".
+        """
+        ...
+    def to_dict(self) -> dict:
+        """Convert to a dictionary whose offsets of elements are in bytes, as recorded in its "offset_unit" key."""
+        ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(dat: str) -> RichText: ...
+    def to_bytes(self) -> bytes:
+        """Encode in the versioned binary format."""
+        ...
+    @staticmethod
+    def from_bytes(dat: bytes) -> RichText: ...
     @staticmethod
-    def from_dict(c: dict) -> RichText: ...
+    def from_dict(c: dict) -> RichText:
+        """Create from a dictionary of `to_dict`, whose offsets are in the unit of its "offset_unit" key ("byte", "codepoint" or "utf16"), or in bytes if it has no such key."""
+        ...
+
+class FrozenRichText(RichText):
+    """Read-only copy of a rich text, e.g., the value of a cell or the heading of a context. Its `set_element_by_id`, `set_element_attr_by_id` and `__setstate__` raise a TypeError; `copy.copy` and pickling return a mutable `RichText`."""
 
 class RichTextElement:
+    """An HTML element of a rich text, covering the text from `start` to `end`.
+
+    Elements returned by `RichText.get_element_by_id` have their offsets in the requested unit (code points by default) and elements given to the functions of `RichText.remove_elements` have their offsets in code points.
+    """
+
     @property
     def tag(self) -> str: ...
     @property
@@ -171,5 +599,27 @@ class RichTextElement:
     def get_attr(self, name: str) -> str: ...
     def has_attr(self, name: str) -> bool: ...
     def to_dict(self) -> dict: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(dat: str) -> RichTextElement: ...
+    def to_bytes(self) -> bytes:
+        """Encode in the versioned binary format."""
+        ...
+    @staticmethod
+    def from_bytes(dat: bytes) -> RichTextElement: ...
     @staticmethod
     def from_dict(c: dict) -> RichTextElement: ...
+
+def tables_to_arrow(tables: list[Table]) -> pyarrow.RecordBatch:
+    """Convert tables to a record batch with one record per cell: table_id, row, col, text, is_header, rowspan, colspan and html (inner HTML of the cell).
+
+    The data is handed to pyarrow without being copied. For cells of a spanned table, rowspan and colspan are the spans of the merged cell they come from. Only available when rsoup is built with the `arrow` feature.
+    """
+    ...
+
+def write_parquet(tables: list[Table], outfile: str) -> None:
+    """Write tables to a Parquet file (zstd compressed) with one record per cell, in the format of `tables_to_arrow`.
+
+    Only available when rsoup is built with the `arrow` feature.
+    """
+    ...
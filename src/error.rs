@@ -1,8 +1,16 @@
 use postcard;
-use pyo3::PyErr;
 use thiserror::Error;
 
+#[cfg(feature = "python")]
+use pyo3::{
+    exceptions::{PyIndexError, PyValueError},
+    type_object::PyTypeObject,
+    PyErr, Python,
+};
+
+#[cfg(feature = "python")]
 pyo3::create_exception!(rsoup, OverlapSpanPyError, pyo3::exceptions::PyException);
+#[cfg(feature = "python")]
 pyo3::create_exception!(rsoup, InvalidCellSpanPyError, pyo3::exceptions::PyException);
 
 /// Represent possible errors returned by this library.
@@ -11,8 +19,16 @@ pub enum RSoupError {
     #[error("InvalidHTMLStructureError: '{0}'")]
     InvalidHTMLStructureError(&'static str),
 
-    #[error("OverlapSpanError: '{0}'")]
-    OverlapSpanError(String),
+    /// A cell's colspan runs into a position already taken by a cell spanning from the rows above.
+    /// `row` and `col` are the index of the offending cell in `Table.rows` and `Row.cells`.
+    #[error("OverlapSpanError: cell ({row}, {col}) with rowspan={rowspan} and colspan={colspan} overlaps another cell in table '{table_id}'")]
+    OverlapSpanError {
+        table_id: String,
+        row: usize,
+        col: usize,
+        rowspan: u16,
+        colspan: u16,
+    },
 
     #[error("InvalidRowSpanError: '{0}'")]
     InvalidRowSpanError(String),
@@ -20,24 +36,128 @@ pub enum RSoupError {
     #[error("InvalidColSpanError: '{0}'")]
     InvalidColSpanError(String),
 
+    /// A cell that is not the last cell of its row spans beyond the number of columns of the table.
+    /// `row` and `col` are the index of the offending cell in `Table.rows` and `Row.cells`.
+    #[error("InvalidCellSpanError: cell ({row}, {col}) with rowspan={rowspan} and colspan={colspan} spans beyond the last column in table '{table_id}'")]
+    InvalidCellSpanError {
+        table_id: String,
+        row: usize,
+        col: usize,
+        rowspan: u16,
+        colspan: u16,
+    },
+
+    /// Binary data that cannot be decoded as the expected kind of data
+    #[error("InvalidFormatError: cannot decode {kind} data of format version {version}: {reason}")]
+    InvalidFormatError {
+        kind: &'static str,
+        version: u16,
+        reason: String,
+    },
+
+    /// Binary data written by a newer version of the library
+    #[error("UnsupportedFormatVersionError: {kind} data has format version {version} but this version of rsoup only supports up to version {max_version}")]
+    UnsupportedFormatVersionError {
+        kind: &'static str,
+        version: u16,
+        max_version: u16,
+    },
+
+    /// A range of a text that is out of bounds, reversed, or not on character boundaries.
+    /// Offsets are in bytes.
+    #[error(
+        "InvalidTextRangeError: range {start}..{end} is not a valid range of a text of {len} bytes"
+    )]
+    InvalidTextRangeError {
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+
+    /// An offset that is out of a text or not on a character boundary, `unit` is the unit of
+    /// the offset: "byte", "codepoint" or "utf16"
+    #[error("InvalidTextOffsetError: {offset} is not a valid {unit} offset of the text")]
+    InvalidTextOffsetError { offset: usize, unit: &'static str },
+
     /// Represents all other cases of `std::io::Error`.
     #[error(transparent)]
     IOError(#[from] std::io::Error),
 
     /// PyO3 error
+    #[cfg(feature = "python")]
     #[error(transparent)]
     PyErr(#[from] pyo3::PyErr),
 
     #[error(transparent)]
     PostcardError(#[from] postcard::Error),
+
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    ArrowError(#[from] arrow::error::ArrowError),
+
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    ParquetError(#[from] parquet::errors::ParquetError),
 }
 
+#[cfg(feature = "python")]
 pub fn into_pyerr<E: Into<RSoupError>>(err: E) -> PyErr {
-    let hderr = err.into();
-    if let RSoupError::PyErr(e) = hderr {
-        e
-    } else {
-        let anyerror: anyhow::Error = hderr.into();
-        anyerror.into()
+    match err.into() {
+        RSoupError::PyErr(e) => e,
+        hderr @ RSoupError::OverlapSpanError { .. } => into_span_pyerr::<OverlapSpanPyError>(hderr),
+        hderr @ RSoupError::InvalidCellSpanError { .. } => {
+            into_span_pyerr::<InvalidCellSpanPyError>(hderr)
+        }
+        hderr @ (RSoupError::InvalidFormatError { .. }
+        | RSoupError::UnsupportedFormatVersionError { .. }
+        | RSoupError::SerdeJsonError(_)) => PyValueError::new_err(hderr.to_string()),
+        hderr @ (RSoupError::InvalidTextRangeError { .. }
+        | RSoupError::InvalidTextOffsetError { .. }) => PyIndexError::new_err(hderr.to_string()),
+        hderr => {
+            let anyerror: anyhow::Error = hderr.into();
+            anyerror.into()
+        }
     }
 }
+
+/// Convert a span error into a python exception of type `T`, exposing the location
+/// of the offending cell as attributes of the exception.
+#[cfg(feature = "python")]
+fn into_span_pyerr<T: PyTypeObject>(err: RSoupError) -> PyErr {
+    let msg = err.to_string();
+    let (table_id, row, col, rowspan, colspan) = match err {
+        RSoupError::OverlapSpanError {
+            table_id,
+            row,
+            col,
+            rowspan,
+            colspan,
+        }
+        | RSoupError::InvalidCellSpanError {
+            table_id,
+            row,
+            col,
+            rowspan,
+            colspan,
+        } => (table_id, row, col, rowspan, colspan),
+        _ => unreachable!(),
+    };
+
+    Python::with_gil(|py| {
+        let pyerr = PyErr::new::<T, _>(msg);
+        let value = pyerr.value(py);
+        let res = value
+            .setattr("table_id", table_id)
+            .and_then(|_| value.setattr("row", row))
+            .and_then(|_| value.setattr("col", col))
+            .and_then(|_| value.setattr("rowspan", rowspan))
+            .and_then(|_| value.setattr("colspan", colspan));
+        match res {
+            Ok(_) => pyerr,
+            Err(e) => e,
+        }
+    })
+}
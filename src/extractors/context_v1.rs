@@ -14,11 +14,13 @@ use crate::{
 use anyhow::Result;
 use ego_tree::NodeRef;
 use hashbrown::HashSet;
-use pyo3::prelude::*;
 use scraper::Node;
 
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
 #[derive(Clone)]
-#[pyclass(module = "rsoup.core")]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
 pub struct ContextExtractor {
     // do not include those tags in the rich text
     ignored_tags: HashSet<String>,
@@ -31,6 +33,7 @@ pub struct ContextExtractor {
     only_keep_inline_tags: bool,
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl ContextExtractor {
     #[new]
@@ -42,7 +45,25 @@ impl ContextExtractor {
         header_elements = "None",
         only_keep_inline_tags = "true"
     )]
-    fn new(
+    fn py_new(
+        ignored_tags: Option<Vec<&str>>,
+        discard_tags: Option<Vec<&str>>,
+        same_content_level_elements: Option<Vec<&str>>,
+        header_elements: Option<Vec<&str>>,
+        only_keep_inline_tags: bool,
+    ) -> Self {
+        ContextExtractor::new(
+            ignored_tags,
+            discard_tags,
+            same_content_level_elements,
+            header_elements,
+            only_keep_inline_tags,
+        )
+    }
+}
+
+impl ContextExtractor {
+    pub fn new(
         ignored_tags: Option<Vec<&str>>,
         discard_tags: Option<Vec<&str>>,
         same_content_level_elements: Option<Vec<&str>>,
@@ -82,9 +103,8 @@ impl ContextExtractor {
             only_keep_inline_tags,
         }
     }
-}
 
-impl ContextExtractor {
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> ContextExtractor {
         let discard_tags = HashSet::from_iter(
             ["script", "style", "noscript", "table"]
@@ -123,7 +143,6 @@ impl ContextExtractor {
     /// is often used as block element so this extractor put it in another line.
     pub fn extract_context<'s>(
         &self,
-        py: Python,
         table_el: NodeRef<'s, Node>,
     ) -> Result<Vec<ContentHierarchy>> {
         let (tree_before, tree_after) = self.locate_content_before_and_after(table_el)?;
@@ -136,17 +155,13 @@ impl ContextExtractor {
         // self.flatten_tree(&tree_before, &mut context_before);
         // self.flatten_tree(&tree_after, &mut context_after);
 
-        let mut context = vec![ContentHierarchy::new(0, Py::new(py, RichText::empty())?)];
+        let mut context = vec![ContentHierarchy::new(0, RichText::empty())];
         for c in context_before {
             if self.header_elements.contains(c.get_tag()) {
                 let header = c.get_tag()[1..].parse::<usize>().unwrap();
-                context.push(ContentHierarchy::new(header, Py::new(py, c)?));
+                context.push(ContentHierarchy::new(header, c));
             } else {
-                context
-                    .last_mut()
-                    .unwrap()
-                    .content_before
-                    .push(Py::new(py, c)?);
+                context.last_mut().unwrap().content_before.push(c);
                 continue;
             }
         }
@@ -163,12 +178,11 @@ impl ContextExtractor {
         }
         rev_context.reverse();
         context = rev_context;
-        context.last_mut().unwrap().content_after.extend(
-            context_after
-                .into_iter()
-                .map(|c| Py::new(py, c))
-                .collect::<PyResult<Vec<_>>>()?,
-        );
+        context
+            .last_mut()
+            .unwrap()
+            .content_after
+            .extend(context_after);
 
         Ok(context)
     }
@@ -182,7 +196,7 @@ impl ContextExtractor {
                 InvState::Entering(nodeid) => {
                     let node = tree.get_node(nodeid);
                     let node_children = tree.get_child_ids(nodeid);
-                    if node_children.len() == 0 {
+                    if node_children.is_empty() {
                         self.flatten_node(node, output);
                         continue;
                     }
@@ -218,7 +232,7 @@ impl ContextExtractor {
                                     continue;
                                 }
 
-                                if pending_ops.len() > 0 {
+                                if !pending_ops.is_empty() {
                                     let rich_text = get_rich_text_from_seq(
                                         pending_ops,
                                         &self.ignored_tags,
@@ -227,7 +241,7 @@ impl ContextExtractor {
                                         &self.header_elements,
                                     );
                                     if self.is_text_interesting(&rich_text) {
-                                        if next_invs.len() > 0 {
+                                        if !next_invs.is_empty() {
                                             exiting_state.push(rich_text);
                                         } else {
                                             output.push(rich_text);
@@ -246,7 +260,7 @@ impl ContextExtractor {
                         }
                     }
 
-                    if pending_ops.len() > 0 {
+                    if !pending_ops.is_empty() {
                         let rich_text = get_rich_text_from_seq(
                             pending_ops,
                             &self.ignored_tags,
@@ -255,7 +269,7 @@ impl ContextExtractor {
                             &self.header_elements,
                         );
                         if self.is_text_interesting(&rich_text) {
-                            if next_invs.len() > 0 {
+                            if !next_invs.is_empty() {
                                 exiting_state.push(rich_text);
                             } else {
                                 output.push(rich_text);
@@ -264,7 +278,7 @@ impl ContextExtractor {
                         pending_ops = Vec::new();
                     }
 
-                    if next_invs.invocations.len() > 0 {
+                    if !next_invs.invocations.is_empty() {
                         inv_tree.add_recur_invocations(
                             &inv,
                             exiting_state,
@@ -324,7 +338,7 @@ impl ContextExtractor {
                                             continue;
                                         }
 
-                                        if pending_ops.len() > 0 {
+                                        if !pending_ops.is_empty() {
                                             let rich_text = get_rich_text_from_seq(
                                                 pending_ops,
                                                 &self.ignored_tags,
@@ -333,7 +347,7 @@ impl ContextExtractor {
                                                 &self.header_elements,
                                             );
                                             if self.is_text_interesting(&rich_text) {
-                                                if next_invs.len() > 0 {
+                                                if !next_invs.is_empty() {
                                                     exiting_state.push(rich_text);
                                                 } else {
                                                     output.push(rich_text);
@@ -352,7 +366,7 @@ impl ContextExtractor {
                                 }
                             }
 
-                            if pending_ops.len() > 0 {
+                            if !pending_ops.is_empty() {
                                 let rich_text = get_rich_text_from_seq(
                                     pending_ops,
                                     &self.ignored_tags,
@@ -361,7 +375,7 @@ impl ContextExtractor {
                                     &self.header_elements,
                                 );
                                 if self.is_text_interesting(&rich_text) {
-                                    if next_invs.len() > 0 {
+                                    if !next_invs.is_empty() {
                                         exiting_state.push(rich_text);
                                     } else {
                                         output.push(rich_text);
@@ -370,7 +384,7 @@ impl ContextExtractor {
                                 pending_ops = Vec::new();
                             }
                             // println!(">>> Before add recur.{}", inv_tree.debug_info());
-                            if next_invs.invocations.len() > 0 {
+                            if !next_invs.invocations.is_empty() {
                                 inv_tree.add_recur_invocations(
                                     &inv,
                                     exiting_state,
@@ -414,7 +428,7 @@ impl ContextExtractor {
     ) {
         let node = tree.get_node(nodeid);
         let node_children = tree.get_child_ids(nodeid);
-        if node_children.len() == 0 {
+        if node_children.is_empty() {
             self.flatten_node_recur(node, output);
             return;
         }
@@ -442,7 +456,7 @@ impl ContextExtractor {
                         continue;
                     }
 
-                    if pending_ops.len() > 0 {
+                    if !pending_ops.is_empty() {
                         let rich_text = get_rich_text_from_seq(
                             pending_ops,
                             &self.ignored_tags,
@@ -462,7 +476,7 @@ impl ContextExtractor {
             }
         }
 
-        if pending_ops.len() > 0 {
+        if !pending_ops.is_empty() {
             let rich_text = get_rich_text_from_seq(
                 pending_ops,
                 &self.ignored_tags,
@@ -507,7 +521,7 @@ impl ContextExtractor {
                                 continue;
                             }
 
-                            if pending_ops.len() > 0 {
+                            if !pending_ops.is_empty() {
                                 let rich_text = get_rich_text_from_seq(
                                     pending_ops,
                                     &self.ignored_tags,
@@ -527,7 +541,7 @@ impl ContextExtractor {
                     }
                 }
 
-                if pending_ops.len() > 0 {
+                if !pending_ops.is_empty() {
                     let rich_text = get_rich_text_from_seq(
                         pending_ops,
                         &self.ignored_tags,
@@ -555,6 +569,7 @@ impl ContextExtractor {
     /// before this element (we are doing another filter outside of this function in `self.extract`).
     ///     * to determine the content after the element, we consider only the siblings
     /// and stop before they hit a block element (not all block elements) that may be in the same level such as table, etc.
+    #[allow(clippy::type_complexity)]
     pub fn locate_content_before_and_after<'s>(
         &self,
         element: NodeRef<'s, Node>,
@@ -616,6 +631,6 @@ impl ContextExtractor {
 
     // test if the text is interesting
     pub fn is_text_interesting(&self, text: &RichText) -> bool {
-        return !(text.text.is_empty() && text.element.len() == 1 && text.get_tag() == PSEUDO_TAG);
+        !(text.text.is_empty() && text.element.len() == 1 && text.get_tag() == PSEUDO_TAG)
     }
 }
@@ -7,7 +7,7 @@ use pyo3::{
     prelude::*,
     types::{PyList, PyString},
 };
-use scraper::{node::Attrs, CaseSensitivity, ElementRef, Node, Selector};
+use scraper::{node::Attrs, CaseSensitivity, ElementRef, Selector};
 
 #[pyclass(module = "rsoup.core", unsendable)]
 pub struct ElementRefView(pub ElementRef<'static>);
@@ -1,28 +1,39 @@
 use scraper::Html;
 
 pub mod context_v1;
+#[cfg(feature = "python")]
 pub mod elementrefview;
 pub mod table;
 pub mod text;
 
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
 use scraper::Selector;
 
+#[cfg(feature = "python")]
 use self::elementrefview::ElementRefView;
 
-#[pyclass(module = "rsoup.core", unsendable)]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core", unsendable))]
 pub struct Document {
     pub url: String,
     pub html: Html,
 }
 
-#[pymethods]
 impl Document {
-    #[new]
     pub fn new(url: String, doc: String) -> Self {
         let html = Html::parse_document(&doc);
         Document { url, html }
     }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Document {
+    #[new]
+    fn py_new(url: String, doc: String) -> Self {
+        Document::new(url, doc)
+    }
 
     pub fn select(&self, query: &str) -> PyResult<Vec<ElementRefView>> {
         let selector = Selector::parse(query).map_err(|_err| {
@@ -1,18 +1,29 @@
-use crate::error::{InvalidCellSpanPyError, OverlapSpanPyError, RSoupError};
+use crate::error::RSoupError;
 use crate::extractors::context_v1::ContextExtractor;
 use crate::extractors::text::{get_rich_text, get_text};
 use crate::extractors::Document;
 use crate::misc::convert_attrs;
 use crate::misc::url_converter::URLConverter;
-use crate::models::table::{Cell, Row, Table};
-use anyhow::{bail, Result};
-use ego_tree::NodeRef;
-use hashbrown::HashSet;
-use pyo3::prelude::*;
-use scraper::{ElementRef, Node, Selector};
+use crate::models::extraction_report::{ExtractionReport, SkipReason, TableReport};
+use crate::models::table::{
+    Cell, Column, Row, RowSection, SpanMode, Table, MAX_COLSPAN, MAX_ROWSPAN,
+};
+use anyhow::Result;
+use ego_tree::{NodeId, NodeRef};
+use hashbrown::{HashMap, HashSet};
+use scraper::{node::Element, ElementRef, Node, Selector};
 use url::Url;
 
-#[pyclass(module = "rsoup.core")]
+#[cfg(feature = "python")]
+use crate::models::table::PyTable;
+
+lazy_static! {
+    static ref TABLE_SELECTOR: Selector = Selector::parse("table").unwrap();
+}
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
 pub struct TableExtractor {
     ignored_tags: HashSet<String>,
     discard_tags: HashSet<String>,
@@ -20,8 +31,12 @@ pub struct TableExtractor {
     only_keep_inline_tags: bool,
     context_extractor: ContextExtractor,
     html_error_forgiveness: bool,
+    // extract tables that contain other tables, nested tables are attached to their cells
+    extract_nested_tables: bool,
+    span_mode: SpanMode,
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl TableExtractor {
     #[new]
@@ -31,8 +46,75 @@ impl TableExtractor {
         discard_tags = "None",
         keep_tags = "None",
         only_keep_inline_tags = "true",
-        html_error_forgiveness = "true"
+        html_error_forgiveness = "true",
+        extract_nested_tables = "false",
+        span_mode = "\"default\""
     )]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        context_extractor: ContextExtractor,
+        ignored_tags: Option<Vec<&str>>,
+        discard_tags: Option<Vec<&str>>,
+        keep_tags: Option<Vec<&str>>,
+        only_keep_inline_tags: bool,
+        html_error_forgiveness: bool,
+        extract_nested_tables: bool,
+        span_mode: &str,
+    ) -> PyResult<Self> {
+        Ok(TableExtractor::new(
+            context_extractor,
+            ignored_tags,
+            discard_tags,
+            keep_tags,
+            only_keep_inline_tags,
+            html_error_forgiveness,
+            extract_nested_tables,
+            span_mode.parse().map_err(PyValueError::new_err)?,
+        ))
+    }
+
+    #[args(auto_span = "true", auto_pad = "true", extract_context = "true")]
+    fn extract(
+        &self,
+        url: String,
+        doc: String,
+        auto_span: bool,
+        auto_pad: bool,
+        extract_context: bool,
+    ) -> PyResult<Vec<PyTable>> {
+        Ok(self
+            .extract_tables(
+                &Document::new(url, doc),
+                auto_span,
+                auto_pad,
+                extract_context,
+            )?
+            .into_iter()
+            .map(PyTable::from)
+            .collect())
+    }
+
+    #[args(auto_span = "true", auto_pad = "true", extract_context = "true")]
+    fn extract_with_report(
+        &self,
+        url: String,
+        doc: String,
+        auto_span: bool,
+        auto_pad: bool,
+        extract_context: bool,
+    ) -> PyResult<(Vec<PyTable>, ExtractionReport)> {
+        let (tables, report) = self.extract_tables_with_report(
+            &Document::new(url, doc),
+            auto_span,
+            auto_pad,
+            extract_context,
+        )?;
+        Ok((tables.into_iter().map(PyTable::from).collect(), report))
+    }
+}
+
+impl TableExtractor {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         context_extractor: ContextExtractor,
         ignored_tags: Option<Vec<&str>>,
@@ -40,6 +122,8 @@ impl TableExtractor {
         keep_tags: Option<Vec<&str>>,
         only_keep_inline_tags: bool,
         html_error_forgiveness: bool,
+        extract_nested_tables: bool,
+        span_mode: SpanMode,
     ) -> Self {
         let discard_tags_ = HashSet::from_iter(
             discard_tags
@@ -67,145 +151,218 @@ impl TableExtractor {
             only_keep_inline_tags,
             context_extractor,
             html_error_forgiveness,
+            extract_nested_tables,
+            span_mode,
         }
     }
 
-    #[args(auto_span = "true", auto_pad = "true", extract_context = "true")]
-    fn extract(
+    /// Extract tables from HTML.
+    pub fn extract_tables(
         &self,
-        py: Python,
-        url: String,
-        doc: String,
+        doc: &Document,
         auto_span: bool,
         auto_pad: bool,
         extract_context: bool,
-    ) -> PyResult<Vec<Table>> {
-        Ok(self.extract_tables(
-            py,
-            &Document::new(url, doc),
-            auto_span,
-            auto_pad,
-            extract_context,
-        )?)
+    ) -> Result<Vec<Table>> {
+        let (tables, _report) =
+            self.extract_tables_with_report(doc, auto_span, auto_pad, extract_context)?;
+        Ok(tables)
     }
-}
 
-impl TableExtractor {
-    /// Extract tables from HTML.
-    pub fn extract_tables<'t>(
+    /// Extract tables from HTML, and report what happened to every `<table>` element
+    /// of the document, including the ones that are skipped.
+    ///
+    /// When nested tables are enabled, only the outermost tables are returned, and the
+    /// tables inside their cells are available in [`Cell::nested_tables`].
+    pub fn extract_tables_with_report(
         &self,
-        py: Python,
-        doc: &'t Document,
+        doc: &Document,
         auto_span: bool,
         auto_pad: bool,
         extract_context: bool,
-    ) -> Result<Vec<Table>> {
+    ) -> Result<(Vec<Table>, ExtractionReport)> {
         let tree = &doc.html;
+        let url = Url::parse(&doc.url)?;
+
+        let mut report = ExtractionReport::default();
+        let mut report_index = HashMap::new();
+        for (index, el) in tree.select(&TABLE_SELECTOR).enumerate() {
+            report_index.insert(el.id(), index);
+            report.tables.push(TableReport {
+                index,
+                table_id: None,
+                skip_reason: None,
+                errors: vec![],
+            });
+        }
 
-        let selector = Selector::parse("table").unwrap();
         let mut tables = vec![];
         let mut table_els = vec![];
-        let mut table_nos = vec![];
 
-        for el in tree.select(&selector) {
-            if el.select(&selector).next().is_some() {
-                continue;
+        if self.extract_nested_tables {
+            let mut table_no = 0;
+            for el in tree.select(&TABLE_SELECTOR) {
+                if get_parent_table(el).is_some() {
+                    continue;
+                }
+                let mut ctx = NestedTableContext {
+                    doc,
+                    url: &url,
+                    auto_span,
+                    auto_pad,
+                    report_index: &report_index,
+                    report: &mut report,
+                    table_no: &mut table_no,
+                };
+                if let Some(table) = self.extract_table_tree(el, None, &mut ctx)? {
+                    tables.push(table);
+                    table_els.push(el);
+                }
             }
-            let table = self.extract_non_nested_table(py, el)?;
-            // skip if no rows or columns
-            if table.rows.len() == 0 || table.rows.iter().all(|r| r.borrow(py).cells.len() == 0) {
-                continue;
+
+            // tables that are not reachable from the cells of the extracted tables
+            for table_report in report.tables.iter_mut() {
+                if table_report.skip_reason.is_none() && table_report.table_id.is_none() {
+                    table_report.skip_reason = Some(SkipReason::ParentSkipped);
+                }
             }
-            tables.push(table);
-            table_els.push(el);
-            table_nos.push(table_nos.len());
-        }
+        } else {
+            // position of each table in the report
+            let mut table_reports = vec![];
 
-        if auto_span {
-            let mut new_tables = Vec::with_capacity(tables.len());
-            let mut new_table_els = Vec::with_capacity(tables.len());
-            let mut new_table_nos = Vec::with_capacity(tables.len());
-
-            for (i, tbl) in tables.iter().enumerate() {
-                match tbl.span(py) {
-                    Ok(new_tbl) => {
-                        new_tables.push(new_tbl);
-                        new_table_els.push(table_els[i]);
-                        new_table_nos.push(i);
-                    }
-                    Err(err) => {
-                        if !err.is_instance_of::<OverlapSpanPyError>(py)
-                            && !err.is_instance_of::<InvalidCellSpanPyError>(py)
-                        {
-                            bail!(err);
+            for el in tree.select(&TABLE_SELECTOR) {
+                let table_report = &mut report.tables[report_index[&el.id()]];
+                if el.select(&TABLE_SELECTOR).next().is_some() {
+                    table_report.skip_reason = Some(SkipReason::NestedTable);
+                    continue;
+                }
+                let table = self.extract_non_nested_table(el)?;
+                // skip if no rows or columns
+                if is_empty_table(&table) {
+                    table_report.skip_reason = Some(SkipReason::NoCells);
+                    continue;
+                }
+                tables.push(table);
+                table_els.push(el);
+                table_reports.push(table_report.index);
+            }
+
+            // update table id, do it before spanning so that errors can refer to the table
+            for (i, tbl) in tables.iter_mut().enumerate() {
+                tbl.id = get_table_id(&url, i)?;
+                tbl.url = doc.url.to_owned();
+                report.tables[table_reports[i]].table_id = Some(tbl.id.clone());
+            }
+
+            if auto_span {
+                let mut new_tables = Vec::with_capacity(tables.len());
+                let mut new_table_els = Vec::with_capacity(tables.len());
+
+                for (i, tbl) in tables.iter().enumerate() {
+                    match tbl.span_with_mode(self.span_mode) {
+                        Ok(new_tbl) => {
+                            new_tables.push(new_tbl);
+                            new_table_els.push(table_els[i]);
+                        }
+                        Err(
+                            err @ RSoupError::OverlapSpanError { .. }
+                            | err @ RSoupError::InvalidCellSpanError { .. },
+                        ) => {
+                            let table_report = &mut report.tables[table_reports[i]];
+                            table_report.skip_reason = Some(SkipReason::SpanError);
+                            table_report.errors.push(err.to_string());
                         }
+                        Err(err) => return Err(err.into()),
                     }
                 }
+                tables = new_tables;
+                table_els = new_table_els;
             }
-            tables = new_tables;
-            table_els = new_table_els;
-            table_nos = new_table_nos;
-        }
-
-        if auto_pad {
-            tables = tables
-                .into_iter()
-                .map(|tbl| Ok(tbl.pad(py)?.unwrap_or(tbl)))
-                .collect::<PyResult<Vec<_>>>()?
-        }
 
-        if extract_context {
-            for i in 0..tables.len() {
-                tables[i].context = self
-                    .context_extractor
-                    .extract_context(py, *table_els[i])?
+            if auto_pad {
+                tables = tables
                     .into_iter()
-                    .map(|x| Py::new(py, x))
-                    .collect::<PyResult<Vec<_>>>()?;
+                    .map(|tbl| tbl.pad().unwrap_or(tbl))
+                    .collect::<Vec<_>>();
             }
         }
 
-        // update table id
-        let mut url = Url::parse(&doc.url)?;
-        let mut query = match url.query() {
-            None => "table_no=".as_bytes().to_vec(),
-            Some(q) => {
-                let mut v = q.as_bytes().to_vec();
-                v.extend_from_slice("&table_no=".as_bytes());
-                v
+        if extract_context {
+            for i in 0..tables.len() {
+                tables[i].context = self.context_extractor.extract_context(*table_els[i])?;
             }
-        };
-        let query_len = query.len();
-
-        for (i, tbl) in tables.iter_mut().enumerate() {
-            query.extend_from_slice(table_nos[i].to_string().as_bytes());
-            url.set_query(Some(std::str::from_utf8(&query)?));
-            tbl.id = url.as_str().to_owned();
-            query.truncate(query_len);
-            tbl.url = doc.url.to_owned();
         }
 
         // convert relative urls to absolute urls
         let url_converter = URLConverter::new(doc.url.to_owned())?;
         for table in &mut tables {
-            for row in &mut table.rows {
-                for cell in &mut (row.borrow_mut(py)).cells {
-                    url_converter
-                        .normalize_rich_text(&mut *cell.borrow_mut(py).value.borrow_mut(py));
+            normalize_table_urls(&url_converter, table);
+        }
+
+        Ok((tables, report))
+    }
+
+    /// Extract a table and, recursively, the tables nested in its cells.
+    ///
+    /// Return None if the table is skipped, the reason is recorded in the report.
+    fn extract_table_tree(
+        &self,
+        table_el: ElementRef,
+        parent_id: Option<&str>,
+        ctx: &mut NestedTableContext,
+    ) -> Result<Option<Table>> {
+        let report_index = ctx.report_index[&table_el.id()];
+        let (mut table, cell_els) = self.extract_table_and_cell_elements(table_el)?;
+        if is_empty_table(&table) {
+            ctx.report.tables[report_index].skip_reason = Some(SkipReason::NoCells);
+            return Ok(None);
+        }
+
+        table.id = get_table_id(ctx.url, *ctx.table_no)?;
+        table.url = ctx.doc.url.to_owned();
+        table.parent_id = parent_id.map(str::to_owned);
+        ctx.report.tables[report_index].table_id = Some(table.id.clone());
+        *ctx.table_no += 1;
+
+        for (row, row_cell_els) in table.rows.iter_mut().zip(cell_els) {
+            for (cell, cell_el) in row.cells.iter_mut().zip(row_cell_els) {
+                for nested_el in cell_el.select(&TABLE_SELECTOR) {
+                    if get_parent_table(nested_el).map(|el| el.id()) != Some(table_el.id()) {
+                        continue;
+                    }
+                    if let Some(nested_table) =
+                        self.extract_table_tree(nested_el, Some(&table.id), ctx)?
+                    {
+                        cell.nested_tables.push(nested_table);
+                    }
                 }
             }
+        }
 
-            for content in &mut table.context {
-                for line in &mut content.borrow_mut(py).content_before {
-                    url_converter.normalize_rich_text(&mut *line.borrow_mut(py));
-                }
-                for line in &mut content.borrow_mut(py).content_after {
-                    url_converter.normalize_rich_text(&mut *line.borrow_mut(py));
+        if ctx.auto_span {
+            match table.span_with_mode(self.span_mode) {
+                Ok(new_table) => table = new_table,
+                Err(
+                    err @ RSoupError::OverlapSpanError { .. }
+                    | err @ RSoupError::InvalidCellSpanError { .. },
+                ) => {
+                    let table_report = &mut ctx.report.tables[report_index];
+                    table_report.skip_reason = Some(SkipReason::SpanError);
+                    table_report.errors.push(err.to_string());
+                    mark_nested_tables_skipped(&table, ctx.report);
+                    return Ok(None);
                 }
+                Err(err) => return Err(err.into()),
             }
         }
 
-        Ok(tables)
+        if ctx.auto_pad {
+            if let Some(new_table) = table.pad() {
+                table = new_table;
+            }
+        }
+
+        Ok(Some(table))
     }
 
     /// Extract content of a single table
@@ -213,10 +370,25 @@ impl TableExtractor {
     /// # Arguments
     ///
     /// * `table_el` - The table element
-    pub fn extract_non_nested_table(&self, py: Python, table_el: ElementRef) -> Result<Table> {
+    pub fn extract_non_nested_table(&self, table_el: ElementRef) -> Result<Table> {
+        Ok(self.extract_table_and_cell_elements(table_el)?.0)
+    }
+
+    /// Extract content of a single table, and return the cell elements of each row
+    /// alongside the table.
+    fn extract_table_and_cell_elements<'t>(
+        &self,
+        table_el: ElementRef<'t>,
+    ) -> Result<(Table, Vec<Vec<ElementRef<'t>>>)> {
         let mut caption: String = "".to_owned();
         let mut rows = vec![];
+        let mut cell_els = vec![];
+        let mut columns = vec![];
 
+        // collect rows of each row group in document order, consecutive rows that are direct
+        // children of the table are in their own tbody group
+        let mut groups: Vec<(RowSection, Vec<ElementRef>)> = vec![];
+        let mut in_implicit_group = false;
         for child_ref in table_el.children() {
             let child = child_ref.value();
             if !child.is_element() {
@@ -224,53 +396,132 @@ impl TableExtractor {
             }
 
             let cel = child.as_element().unwrap();
-            if cel.name() == "caption" {
-                caption = get_text(&child_ref);
+            if cel.name() == "tr" {
+                let row_el = ElementRef::wrap(child_ref).unwrap();
+                match groups.last_mut() {
+                    Some((_, row_els)) if in_implicit_group => row_els.push(row_el),
+                    _ => groups.push((RowSection::TBody, vec![row_el])),
+                }
+                in_implicit_group = true;
                 continue;
             }
+            in_implicit_group = false;
 
-            if cel.name() != "thead" && cel.name() != "tbody" {
-                debug_assert!(cel.name() == "style");
-                continue;
-            }
+            let section = match cel.name() {
+                "caption" => {
+                    caption = get_text(&child_ref);
+                    continue;
+                }
+                "colgroup" => {
+                    let mut has_col = false;
+                    for col_ref in child_ref.children() {
+                        if let Node::Element(col_el) = col_ref.value() {
+                            if col_el.name() == "col" {
+                                columns.push(self.extract_column(col_el)?);
+                                has_col = true;
+                            }
+                        }
+                    }
+                    // span of colgroup is only used when it has no col
+                    if !has_col {
+                        columns.push(self.extract_column(cel)?);
+                    }
+                    continue;
+                }
+                "col" => {
+                    columns.push(self.extract_column(cel)?);
+                    continue;
+                }
+                "thead" => RowSection::THead,
+                "tbody" => RowSection::TBody,
+                "tfoot" => RowSection::TFoot,
+                _ => {
+                    debug_assert!(cel.name() == "style");
+                    continue;
+                }
+            };
 
+            let mut row_els = vec![];
             for row_ref in child_ref.children() {
                 if let Node::Element(row_el) = row_ref.value() {
                     if row_el.name() != "tr" {
                         debug_assert!(row_el.name() == "style");
                         continue;
                     }
+                    row_els.push(ElementRef::wrap(row_ref).unwrap());
+                }
+            }
+            groups.push((section, row_els));
+        }
 
-                    let mut cells = vec![];
-                    for cell_ref in row_ref.children() {
-                        if let Node::Element(cell_el) = cell_ref.value() {
-                            if cell_el.name() != "td" && cell_el.name() != "th" {
-                                debug_assert!(cell_el.name() == "style");
-                                continue;
-                            }
-                            cells.push(Py::new(py, self.extract_cell(py, cell_ref)?)?);
+        // like browsers, the first thead is rendered at the top of the table and the first tfoot
+        // at the bottom, the other row groups are rendered in document order
+        if let Some(i) = groups.iter().position(|g| g.0 == RowSection::THead) {
+            let group = groups.remove(i);
+            groups.insert(0, group);
+        }
+        if let Some(i) = groups.iter().position(|g| g.0 == RowSection::TFoot) {
+            let group = groups.remove(i);
+            groups.push(group);
+        }
+
+        for (group, (section, row_els)) in groups.into_iter().enumerate() {
+            for row_el in row_els {
+                let mut cells = vec![];
+                let mut row_cell_els = vec![];
+                for cell_ref in row_el.children() {
+                    if let Node::Element(cell_el) = cell_ref.value() {
+                        if cell_el.name() != "td" && cell_el.name() != "th" {
+                            debug_assert!(cell_el.name() == "style");
+                            continue;
                         }
+                        cells.push(self.extract_cell(cell_ref)?);
+                        row_cell_els.push(ElementRef::wrap(cell_ref).unwrap());
                     }
-
-                    rows.push(Py::new(
-                        py,
-                        Row {
-                            cells,
-                            attrs: convert_attrs(&row_el.attrs),
-                        },
-                    )?);
                 }
+
+                rows.push(Row {
+                    cells,
+                    attrs: convert_attrs(&row_el.value().attrs),
+                    section,
+                    group,
+                });
+                cell_els.push(row_cell_els);
             }
         }
 
-        Ok(Table {
-            id: String::new(),
-            url: String::new(),
+        let mut table = Table::new(
+            String::new(),
+            String::new(),
             caption,
-            attrs: convert_attrs(&table_el.value().attrs),
-            context: Vec::new(),
+            convert_attrs(&table_el.value().attrs),
+            Vec::new(),
             rows,
-        })
+        );
+        table.columns = columns;
+        Ok((table, cell_els))
+    }
+
+    /// Extract column descriptor from col/colgroup tag
+    fn extract_column(&self, el: &Element) -> Result<Column> {
+        debug_assert!(el.name() == "col" || el.name() == "colgroup");
+
+        let raw_span = el.attr("span").unwrap_or("1").trim();
+        let span = if raw_span.is_empty() {
+            1
+        } else if self.html_error_forgiveness {
+            self.parse_span_attr(raw_span, MAX_COLSPAN)
+        } else {
+            raw_span
+                .parse::<u16>()
+                .map_err(|_| RSoupError::InvalidColSpanError(raw_span.to_owned()))?
+        };
+
+        // browsers treat span = 0 as 1 and limit span like colspan
+        Ok(Column::new(
+            span.clamp(1, MAX_COLSPAN),
+            convert_attrs(&el.attrs),
+        ))
     }
 
     /// Extract cell from td/th tag. This function does not expect a nested table in the cell
@@ -278,7 +529,7 @@ impl TableExtractor {
     /// # Arguments
     ///
     /// * `cell` - td/th tag
-    fn extract_cell(&self, py: Python, cell: NodeRef<Node>) -> Result<Cell> {
+    fn extract_cell(&self, cell: NodeRef<Node>) -> Result<Cell> {
         let el = cell.value().as_element().expect("Expected element");
         debug_assert!(el.name() == "td" || el.name() == "th");
 
@@ -286,41 +537,123 @@ impl TableExtractor {
         let raw_colspan = el.attr("colspan").unwrap_or("1").trim();
         let raw_rowspan = el.attr("rowspan").unwrap_or("1").trim();
 
-        let colspan = if raw_colspan == "" {
+        let colspan = if raw_colspan.is_empty() {
             1
         } else if self.html_error_forgiveness {
-            atoi::atoi::<u16>(raw_colspan.as_bytes()).unwrap_or(1)
+            self.parse_span_attr(raw_colspan, MAX_COLSPAN)
         } else {
             // convert
             raw_colspan
                 .parse::<u16>()
                 .map_err(|_| RSoupError::InvalidColSpanError(raw_colspan.to_owned()))?
         };
-        let rowspan = if raw_rowspan == "" {
+        let rowspan = if raw_rowspan.is_empty() {
             1
         } else if self.html_error_forgiveness {
-            atoi::atoi::<u16>(raw_rowspan.as_bytes()).unwrap_or(1)
+            self.parse_span_attr(raw_rowspan, MAX_ROWSPAN)
         } else {
             raw_rowspan
                 .parse::<u16>()
                 .map_err(|_| RSoupError::InvalidRowSpanError(raw_rowspan.to_owned()))?
         };
 
-        Ok(Cell {
+        Ok(Cell::new(
             is_header,
             rowspan,
             colspan,
-            value: Py::new(
-                py,
-                get_rich_text(
-                    &cell,
-                    &self.ignored_tags,
-                    self.only_keep_inline_tags,
-                    &self.discard_tags,
-                    &self.keep_tags,
-                ),
-            )?,
-            attrs: convert_attrs(&el.attrs),
-        })
+            convert_attrs(&el.attrs),
+            get_rich_text(
+                &cell,
+                &self.ignored_tags,
+                self.only_keep_inline_tags,
+                &self.discard_tags,
+                &self.keep_tags,
+            ),
+        ))
+    }
+
+    /// Parse a span attribute leniently, using only its leading digits. In the browser span mode,
+    /// values that are too large are clamped to `max` like browsers do, otherwise they are
+    /// ignored (i.e., the span is 1).
+    fn parse_span_attr(&self, raw: &str, max: u16) -> u16 {
+        match self.span_mode {
+            SpanMode::Default => atoi::atoi::<u16>(raw.as_bytes()).unwrap_or(1),
+            SpanMode::Browser => match atoi::atoi::<u16>(raw.as_bytes()) {
+                Some(span) => span.min(max),
+                // atoi only fails when there is no digit or when the number overflows
+                None if raw.starts_with(|c: char| c.is_ascii_digit()) => max,
+                None => 1,
+            },
+        }
+    }
+}
+
+/// State shared while extracting a table and its nested tables
+struct NestedTableContext<'a, 'r> {
+    doc: &'a Document,
+    url: &'a Url,
+    auto_span: bool,
+    auto_pad: bool,
+    report_index: &'r HashMap<NodeId, usize>,
+    report: &'r mut ExtractionReport,
+    // number of tables that have been assigned an id
+    table_no: &'r mut usize,
+}
+
+/// Id of a table is the url of the document with the table number in the query
+fn get_table_id(url: &Url, table_no: usize) -> Result<String> {
+    let mut url = url.clone();
+    let query = match url.query() {
+        None => format!("table_no={}", table_no),
+        Some(q) => format!("{}&table_no={}", q, table_no),
+    };
+    url.set_query(Some(&query));
+    Ok(url.as_str().to_owned())
+}
+
+/// Get the closest table that contains the given element
+fn get_parent_table(el: ElementRef) -> Option<ElementRef> {
+    el.ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|ancestor| ancestor.value().name() == "table")
+}
+
+fn is_empty_table(table: &Table) -> bool {
+    table.rows.is_empty() || table.rows.iter().all(|r| r.cells.is_empty())
+}
+
+/// Mark the nested tables of a skipped table as skipped in the report
+fn mark_nested_tables_skipped(table: &Table, report: &mut ExtractionReport) {
+    for row in &table.rows {
+        for cell in &row.cells {
+            for nested_table in &cell.nested_tables {
+                for table_report in report.tables.iter_mut() {
+                    if table_report.table_id.as_ref() == Some(&nested_table.id) {
+                        table_report.skip_reason = Some(SkipReason::ParentSkipped);
+                    }
+                }
+                mark_nested_tables_skipped(nested_table, report);
+            }
+        }
+    }
+}
+
+fn normalize_table_urls(url_converter: &URLConverter, table: &mut Table) {
+    for row in &mut table.rows {
+        for cell in &mut row.cells {
+            url_converter.normalize_rich_text(&mut cell.value);
+            for nested_table in &mut cell.nested_tables {
+                normalize_table_urls(url_converter, nested_table);
+            }
+        }
+    }
+
+    for content in &mut table.context {
+        for line in &mut content.content_before {
+            url_converter.normalize_rich_text(line);
+        }
+        for line in &mut content.content_after {
+            url_converter.normalize_rich_text(line);
+        }
     }
 }
@@ -34,8 +34,8 @@ use crate::models::rich_text::{RichText, RichTextElement, PSEUDO_TAG};
 /// * `only_inline_tags` - whether to only track inline tags
 /// * `discard_tags` - set of tags will be discarded and not included in the text
 /// * `keep_tags` - set of tags will be kept and included in the text
-pub fn get_rich_text<'s>(
-    el: &'s NodeRef<Node>,
+pub fn get_rich_text(
+    el: &NodeRef<Node>,
     ignored_tags: &HashSet<String>,
     only_inline_tags: bool,
     discard_tags: &HashSet<String>,
@@ -118,6 +118,7 @@ pub fn get_rich_text_from_seq(
     )
 }
 
+#[allow(clippy::too_many_arguments, clippy::collapsible_match)]
 fn get_rich_text_from_stream<'s>(
     mut stream: Vec<NodeRef<'s, Node>>,
     mut element: SimpleTree<RichTextElement>,
@@ -188,7 +189,7 @@ fn get_rich_text_from_stream<'s>(
             }
             Node::Text(text) => {
                 // let prior_line = line.clone();
-                line.append(&text);
+                line.append(text);
                 // println!(
                 //     ">>> text: `{}`\n\tline before `{:?}`\n\tline after `{:?}`",
                 //     &text.text.replace("\n", "\\n"),
@@ -214,7 +215,8 @@ fn get_rich_text_from_stream<'s>(
                 // so if it's not our marker, we skip it
                 if stream.len() == stack_ptrs.last().unwrap().0 {
                     // this is our marker, we exit the current element
-                    let mut text_el = element.get_node_mut(stack_ptrs.pop().unwrap().1);
+                    let node_id = stack_ptrs.pop().unwrap().1;
+                    let text_el = element.get_node_mut(node_id);
 
                     // here we re-adjust the range of the element
                     // as previous we use the index of token not index of character
@@ -233,7 +235,7 @@ fn get_rich_text_from_stream<'s>(
                     // empty and the content of the tag must be empty (contradiction), so the content of
                     // the tag must be empty. when it is empty, it may be okay if we just put it in the
                     // end of the previous line as it does not interfere with output text.
-                    let shifted_pos = if paragraph.len() > 0 && line.len() > 0 {
+                    let shifted_pos = if paragraph.len() > 0 && !line.is_empty() {
                         1
                     } else {
                         0
@@ -256,6 +258,9 @@ fn get_rich_text_from_stream<'s>(
                     };
                     text_el.start = start_pos;
                     text_el.end = paragraph.len() + line.len() + shifted_pos;
+                    // empty descendants before the skipped leading space (e.g., <i> in
+                    // `<b><i></i> c</b>`) would start before the element, move them inside it
+                    move_descendants_after(&mut element, node_id, start_pos);
                     // println!(
                     //     ">>> text_el {:?}, start_token: {}, paragraph.tokens: {}",
                     //     text_el,
@@ -276,3 +281,17 @@ fn get_rich_text_from_stream<'s>(
 
     RichText { text, element }
 }
+
+/// Move the descendants of an element that start before `start` to `start`
+fn move_descendants_after(element: &mut SimpleTree<RichTextElement>, node_id: usize, start: usize) {
+    let mut stack = element.get_child_ids(node_id).to_vec();
+    while let Some(id) = stack.pop() {
+        let node = element.get_node_mut(id);
+        if node.start >= start {
+            continue;
+        }
+        node.start = start;
+        node.end = node.end.max(start);
+        stack.extend_from_slice(element.get_child_ids(id));
+    }
+}
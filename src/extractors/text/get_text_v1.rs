@@ -39,7 +39,7 @@ pub fn get_text(el: &NodeRef<Node>) -> String {
                     // create a newline if the current line is not empty
                     // (the empty line will be skipped)
                     let line = process_line(&mut current_line);
-                    if line.len() > 0 {
+                    if !line.is_empty() {
                         lines.push(line);
                     }
                     current_line.clear();
@@ -58,7 +58,7 @@ pub fn get_text(el: &NodeRef<Node>) -> String {
                 // may be we are here because of an iframe (haven't tested) or a marker
                 // we put to breakline after escaping a block element
                 let line = process_line(&mut current_line);
-                if line.len() > 0 {
+                if !line.is_empty() {
                     lines.push(line);
                 }
                 current_line.clear();
@@ -74,9 +74,9 @@ pub fn get_text(el: &NodeRef<Node>) -> String {
         }
     }
 
-    if current_line.len() > 0 {
+    if !current_line.is_empty() {
         let line = process_line(&mut current_line);
-        if line.len() > 0 {
+        if !line.is_empty() {
             lines.push(line);
         }
     }
@@ -89,7 +89,7 @@ pub fn get_text(el: &NodeRef<Node>) -> String {
 /// 2. Leading and trailing spaces are removed.
 #[inline(always)]
 fn process_line(line: &mut [String]) -> String {
-    if line.len() == 0 {
+    if line.is_empty() {
         return String::new();
     }
 
@@ -31,7 +31,7 @@ pub fn get_text(el: &NodeRef<Node>) -> String {
                 if BLOCK_ELEMENTS.contains(node_el.name()) {
                     // create a newline if the current line is not empty
                     // (the empty line will be skipped)
-                    if line.tokens.len() > 0 {
+                    if !line.tokens.is_empty() {
                         paragraph.extend(line.tokens.iter());
                         paragraph.push("\n");
                     }
@@ -45,13 +45,13 @@ pub fn get_text(el: &NodeRef<Node>) -> String {
                 stream.extend(node.children().rev());
             }
             Node::Text(text) => {
-                line.append(&text);
+                line.append(text);
             }
             Node::Document => {
                 // may be we are here because of an iframe (haven't tested) or a marker
                 // we put to breakline after escaping a block element
                 // unimplemented!()
-                if line.tokens.len() > 0 {
+                if !line.tokens.is_empty() {
                     paragraph.extend(line.tokens.iter());
                     paragraph.push("\n");
                 }
@@ -68,7 +68,7 @@ pub fn get_text(el: &NodeRef<Node>) -> String {
         }
     }
 
-    if line.tokens.len() > 0 {
+    if !line.tokens.is_empty() {
         paragraph.extend(line.tokens.iter());
         paragraph.push("\n");
     }
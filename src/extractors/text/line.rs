@@ -1,3 +1,5 @@
+use std::fmt;
+
 /// Represent a line of text that whitespace in text of inline elements are
 /// processed according to the document: https://developer.mozilla.org/en-US/docs/Web/API/Document_Object_Model/Whitespace
 #[derive(Debug, Clone)]
@@ -33,11 +35,6 @@ impl<'s> Line<'s> {
         self.len_before_last_sentence + self.len_last_sentence
     }
 
-    #[allow(dead_code)]
-    pub fn to_string(&self) -> String {
-        self.tokens.join("")
-    }
-
     /// Append a sentence to the line following the HTML whitespace rules.
     ///
     /// 1. Always remove leading spaces
@@ -49,8 +46,8 @@ impl<'s> Line<'s> {
         let trimed_start_sentence = sentence.trim_start();
 
         // don't add an empty string
-        if trimed_start_sentence.len() == 0 {
-            if self.tokens.len() > 0 {
+        if trimed_start_sentence.is_empty() {
+            if !self.tokens.is_empty() {
                 self.has_trailing_space = true;
             }
             return;
@@ -60,7 +57,7 @@ impl<'s> Line<'s> {
 
         // apply a trailing space from the previous sentence, or add a space if the beginning of the new sentence has leading spaces
         if self.has_trailing_space
-            || (self.tokens.len() > 0 && sentence.starts_with(char::is_whitespace))
+            || (!self.tokens.is_empty() && sentence.starts_with(char::is_whitespace))
         {
             self.tokens.push(" ");
             self.len_before_last_sentence += 1;
@@ -69,7 +66,7 @@ impl<'s> Line<'s> {
         self.len_last_sentence = 0;
         // split sentence by space to merge consecutive spaces
         for token in trimed_start_sentence.split(char::is_whitespace) {
-            if token.len() == 0 {
+            if token.is_empty() {
                 continue;
             }
             self.tokens.push(token);
@@ -102,7 +99,7 @@ impl<'s> Paragraph<'s> {
         if line.is_empty() {
             return;
         }
-        if self.tokens.len() > 0 {
+        if !self.tokens.is_empty() {
             self.tokens.push("\n");
             self.len += 1;
         }
@@ -114,8 +111,16 @@ impl<'s> Paragraph<'s> {
     pub fn len(&self) -> usize {
         self.len
     }
+}
+
+impl<'s> fmt::Display for Line<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.tokens.iter().try_for_each(|token| f.write_str(token))
+    }
+}
 
-    pub fn to_string(&self) -> String {
-        self.tokens.join("")
+impl<'s> fmt::Display for Paragraph<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.tokens.iter().try_for_each(|token| f.write_str(token))
     }
 }
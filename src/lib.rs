@@ -1,32 +1,65 @@
+// lints triggered by the code generated by the pyo3 macros
+#![allow(
+    non_local_definitions,
+    unexpected_cfgs,
+    clippy::unnecessary_fallible_conversions
+)]
+
 #[macro_use]
 extern crate lazy_static;
 
-use pyo3::{prelude::*, types::PyList};
-
 pub mod error;
 pub mod extractors;
 pub mod misc;
 pub mod models;
 
-use extractors::elementrefview::RichTextConfig;
-use models::content_hierarchy::ContentHierarchy;
-use models::rich_text::{RichText, RichTextElement};
-use models::table::{Cell, Row, Table};
+#[cfg(feature = "python")]
+use pyo3::{prelude::*, types::PyList};
 
+#[cfg(feature = "python")]
 #[pymodule]
 fn core(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
     m.setattr("__path__", PyList::empty(py))?;
 
-    m.add_class::<Table>()?;
-    m.add_class::<Row>()?;
-    m.add_class::<Cell>()?;
-    m.add_class::<ContentHierarchy>()?;
-    m.add_class::<RichText>()?;
-    m.add_class::<RichTextConfig>()?;
-    m.add_class::<RichTextElement>()?;
+    m.add(
+        "OverlapSpanPyError",
+        py.get_type::<self::error::OverlapSpanPyError>(),
+    )?;
+    m.add(
+        "InvalidCellSpanPyError",
+        py.get_type::<self::error::InvalidCellSpanPyError>(),
+    )?;
+
+    m.add_class::<self::models::table::PyTable>()?;
+    m.add_class::<self::models::table::PyRow>()?;
+    m.add_class::<self::models::table::PyCell>()?;
+    m.add_class::<self::models::table::Column>()?;
+    m.add_class::<self::models::table::TableHeader>()?;
+    m.add_class::<self::models::table::TypedValue>()?;
+    m.add_class::<self::models::table::PyTableWriter>()?;
+    m.add_class::<self::models::table::PyTableReader>()?;
+    m.add_class::<self::models::content_hierarchy::PyContentHierarchy>()?;
+    m.add_class::<self::models::rich_text::RichText>()?;
+    m.add_class::<self::models::rich_text::FrozenRichText>()?;
+    m.add_class::<self::extractors::elementrefview::RichTextConfig>()?;
+    m.add_class::<self::models::rich_text::RichTextElement>()?;
+    m.add_class::<self::models::extraction_report::ExtractionReport>()?;
+    m.add_class::<self::models::extraction_report::TableReport>()?;
     m.add_class::<self::extractors::table::TableExtractor>()?;
     m.add_class::<self::extractors::context_v1::ContextExtractor>()?;
     m.add_class::<self::extractors::Document>()?;
+
+    #[cfg(feature = "arrow")]
+    {
+        m.add_function(wrap_pyfunction!(
+            self::models::table::record_batch::tables_to_arrow,
+            m
+        )?)?;
+        m.add_function(wrap_pyfunction!(
+            self::models::table::record_batch::py_write_parquet,
+            m
+        )?)?;
+    }
     Ok(())
 }
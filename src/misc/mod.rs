@@ -13,6 +13,47 @@ pub fn convert_attrs(attrs: &Attributes) -> HashMap<String, String> {
         .collect::<HashMap<_, _>>()
 }
 
+/// Escape the characters that start or end inline markdown markup (emphasis, code, links)
+pub fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape the characters that are special in HTML text
+pub fn escape_html_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escape the characters that are special in HTML text and in quoted attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 pub struct ChainN<I, V>
 where
     I: Iterator<Item = V>,
@@ -34,7 +75,7 @@ where
             }
             self.index += 1;
         }
-        return None;
+        None
     }
 }
 
@@ -48,6 +89,6 @@ impl<A, B> Enum2<A, B> {
         if let Enum2::Type2(_) = self {
             return true;
         }
-        return false;
+        false
     }
 }
@@ -1,11 +1,25 @@
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 
-#[pyclass(module = "rsoup.core")]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
 pub struct RangeIter {
     pub start: usize,
     pub end: usize,
 }
 
+impl Iterator for RangeIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.start >= self.end {
+            return None;
+        }
+        self.start += 1;
+        Some(self.start - 1)
+    }
+}
+
+#[cfg(feature = "python")]
 #[pymethods]
 impl RangeIter {
     fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
@@ -13,10 +27,6 @@ impl RangeIter {
     }
 
     fn __next__(&mut self) -> Option<usize> {
-        if self.start >= self.end {
-            return None;
-        }
-        self.start += 1;
-        Some(self.start - 1)
+        self.next()
     }
 }
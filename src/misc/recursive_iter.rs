@@ -92,6 +92,7 @@ where
     }
 
     // Move to the next node in the invocation tree.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<InvNode<U, V>> {
         self.stack.pop()
     }
@@ -107,7 +108,7 @@ where
         self.stack.reserve(next_invs.len() + 1);
 
         self.stack.push(InvNode {
-            parent_id: inv.parent_id.clone(),
+            parent_id: inv.parent_id,
             return_id: inv.return_id,
             state: InvState::Exiting(exiting_state),
         });
@@ -130,7 +131,7 @@ where
             buf.push(format!("\n\t- Return Id: {}", inv.return_id));
             match &inv.state {
                 InvState::Entering(_val) => {
-                    buf.push(format!("\n\t- State::Entering"));
+                    buf.push("\n\t- State::Entering".to_string());
                 }
                 InvState::Exiting(val) => {
                     buf.push(format!("\n\t- State::Exiting({:?})", val));
@@ -148,10 +149,16 @@ where
     V: std::fmt::Debug,
 {
     pub fn is_entering(&self) -> bool {
-        match self {
-            InvState::Entering(_) => true,
-            _ => false,
-        }
+        matches!(self, InvState::Entering(_))
+    }
+}
+
+impl<U> Default for InvExitingSeqState<U>
+where
+    U: std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -176,6 +183,11 @@ where
         self.seq.len()
     }
 
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.seq.is_empty()
+    }
+
     #[inline(always)]
     pub fn pop(&mut self) -> U {
         self.n_consumed += 1;
@@ -188,6 +200,15 @@ where
     }
 }
 
+impl<U> Default for RecurInvocationBuilder<U>
+where
+    U: std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<U> RecurInvocationBuilder<U>
 where
     U: std::fmt::Debug,
@@ -204,6 +225,11 @@ where
         self.invocations.len()
     }
 
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.invocations.is_empty()
+    }
+
     #[inline(always)]
     pub fn push(&mut self, return_id: usize, inv: U) {
         self.return_ids.push(return_id);
@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 pub trait ITree<K, V> {
-    fn get_root_id_ref<'s>(&'s self) -> &'s K;
+    fn get_root_id_ref(&self) -> &K;
     fn get_node_by_id_ref<'s>(&'s self, id: &'s K) -> &'s V;
     fn get_child_ids_ref<'s>(&'s self, id: &'s K) -> &'s [K];
 }
@@ -39,7 +39,7 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if self.stack.len() == 0 {
+            if self.stack.is_empty() {
                 if self.inited {
                     return None;
                 }
@@ -89,7 +89,7 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if self.0.stack.len() == 0 {
+            if self.0.stack.is_empty() {
                 if self.0.inited {
                     return None;
                 }
@@ -113,14 +113,14 @@ impl<N> SimpleTree<N> {
 
     pub fn merge_subtree(&mut self, parent_id: usize, mut subtree: SimpleTree<N>) {
         let id_offset = self.nodes.len();
-        self.nodes.extend(subtree.nodes.into_iter());
+        self.nodes.extend(subtree.nodes);
         // update ids of children in node => children in the subtree
         for children in subtree.node2children.iter_mut() {
             for child_id in children {
                 *child_id += id_offset;
             }
         }
-        self.node2children.extend(subtree.node2children.into_iter());
+        self.node2children.extend(subtree.node2children);
         self.node2children[parent_id].push(subtree.root + id_offset);
     }
 
@@ -172,6 +172,7 @@ impl<N> SimpleTree<N> {
     pub fn to_string(&self, key: &dyn Fn(usize) -> String) -> String {
         let mut buffer = Vec::<String>::with_capacity(self.len());
 
+        #[allow(clippy::type_complexity)]
         struct RecurFn<'s> {
             f: &'s dyn Fn(&RecurFn, usize, usize, &mut Vec<String>),
         }
@@ -182,7 +183,7 @@ impl<N> SimpleTree<N> {
                 buffer.push(indent.clone());
                 buffer.push(key(node_id));
 
-                if self.node2children[node_id].len() > 0 {
+                if !self.node2children[node_id].is_empty() {
                     buffer.push(" -> {\n".to_owned());
                     for child_id in self.node2children[node_id].iter() {
                         (func.f)(func, *child_id, depth + 1, buffer);
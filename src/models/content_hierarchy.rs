@@ -1,32 +1,38 @@
-use crate::error::into_pyerr;
+use crate::error::RSoupError;
+use crate::models::format::{self, DataKind};
 use crate::models::rich_text::RichText;
-use postcard::{from_bytes, to_allocvec};
-use pyo3::{prelude::*, types::PyBytes, types::PyDict, types::PyList};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+
+#[cfg(feature = "python")]
+use crate::error::into_pyerr;
+#[cfg(feature = "python")]
+use crate::models::rich_text::FrozenRichText;
+#[cfg(feature = "python")]
+use crate::models::table::PyTable;
+#[cfg(feature = "python")]
+use pyo3::{
+    exceptions::PyIndexError,
+    prelude::*,
+    types::{PyBytes, PyDict, PyList},
+};
 
 /// Content at each level that leads to the table
-#[derive(Clone, Deserialize, Serialize)]
-#[pyclass(module = "rsoup.core")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ContentHierarchy {
     // level of the heading, level 0 indicate the beginning of the document
     // but should not be used
-    #[pyo3(get, set)]
     pub level: usize,
     // title of the level (header)
-    #[pyo3(get, set)]
-    pub heading: Py<RichText>,
+    pub heading: RichText,
     // content of each level (with the trace), the trace includes information
     // of the containing element
-    #[pyo3(get)]
-    pub content_before: Vec<Py<RichText>>,
+    pub content_before: Vec<RichText>,
     // only non empty if this is at the same level of the table (lowest level)
-    #[pyo3(get)]
-    pub content_after: Vec<Py<RichText>>,
+    pub content_after: Vec<RichText>,
 }
 
 impl ContentHierarchy {
-    pub fn new(level: usize, heading: Py<RichText>) -> Self {
+    pub fn new(level: usize, heading: RichText) -> Self {
         ContentHierarchy {
             level,
             heading,
@@ -34,50 +40,186 @@ impl ContentHierarchy {
             content_after: Vec::new(),
         }
     }
+
+    /// Encode in the versioned binary format, see [`crate::models::format`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RSoupError> {
+        format::encode(DataKind::ContentHierarchy, self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<ContentHierarchy, RSoupError> {
+        format::decode(DataKind::ContentHierarchy, bytes)
+    }
+
+    pub fn to_json(&self) -> Result<String, RSoupError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(dat: &str) -> Result<ContentHierarchy, RSoupError> {
+        Ok(serde_json::from_str(dat)?)
+    }
 }
 
-#[pymethods]
-impl ContentHierarchy {
-    #[new]
-    pub fn construct(py: Python) -> Self {
-        ContentHierarchy::new(0, Py::new(py, RichText::empty()).unwrap())
+/// Python wrapper of [`ContentHierarchy`]. A content hierarchy obtained from a table is a view
+/// of the context of the table: reading or modifying it reads or modifies the table. Its texts
+/// are returned as copies.
+#[cfg(feature = "python")]
+#[pyclass(module = "rsoup.core", name = "ContentHierarchy")]
+pub struct PyContentHierarchy {
+    data: ContentHierarchyData,
+}
+
+#[cfg(feature = "python")]
+enum ContentHierarchyData {
+    Owned(ContentHierarchy),
+    InTable { table: Py<PyTable>, index: usize },
+}
+
+#[cfg(feature = "python")]
+impl From<ContentHierarchy> for PyContentHierarchy {
+    fn from(content: ContentHierarchy) -> Self {
+        PyContentHierarchy {
+            data: ContentHierarchyData::Owned(content),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl PyContentHierarchy {
+    /// View of the context at `index` of the table
+    pub fn in_table(table: Py<PyTable>, index: usize) -> Self {
+        PyContentHierarchy {
+            data: ContentHierarchyData::InTable { table, index },
+        }
+    }
+
+    pub fn with_content_hierarchy<R>(
+        &self,
+        py: Python,
+        f: impl FnOnce(&ContentHierarchy) -> R,
+    ) -> PyResult<R> {
+        match &self.data {
+            ContentHierarchyData::Owned(content) => Ok(f(content)),
+            ContentHierarchyData::InTable { table, index } => table
+                .try_borrow(py)?
+                .table
+                .context
+                .get(*index)
+                .map(f)
+                .ok_or_else(|| removed_error(*index)),
+        }
     }
 
+    pub fn with_content_hierarchy_mut<R>(
+        &mut self,
+        py: Python,
+        f: impl FnOnce(&mut ContentHierarchy) -> R,
+    ) -> PyResult<R> {
+        match &mut self.data {
+            ContentHierarchyData::Owned(content) => Ok(f(content)),
+            ContentHierarchyData::InTable { table, index } => table
+                .try_borrow_mut(py)?
+                .table
+                .context
+                .get_mut(*index)
+                .map(f)
+                .ok_or_else(|| removed_error(*index)),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+fn removed_error(index: usize) -> PyErr {
+    PyIndexError::new_err(format!(
+        "The context at index {} is no longer in its table",
+        index
+    ))
+}
+
+#[cfg(feature = "python")]
+impl ContentHierarchy {
     pub fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
         let d = PyDict::new(py);
         d.set_item("level", self.level)?;
-        d.set_item("heading", self.heading.borrow(py).to_dict(py)?)?;
+        d.set_item("heading", self.heading.to_dict(py)?)?;
         d.set_item(
             "content_before",
             self.content_before
                 .iter()
-                .map(|t| t.borrow(py).to_dict(py))
+                .map(|t| t.to_dict(py))
                 .collect::<PyResult<Vec<_>>>()?,
         )?;
         d.set_item(
             "content_after",
             self.content_after
                 .iter()
-                .map(|t| t.borrow(py).to_dict(py))
+                .map(|t| t.to_dict(py))
                 .collect::<PyResult<Vec<_>>>()?,
         )?;
         Ok(d.into_py(py))
     }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyContentHierarchy {
+    #[new]
+    pub fn construct() -> Self {
+        PyContentHierarchy::from(ContentHierarchy::new(0, RichText::empty()))
+    }
+
+    #[getter]
+    fn level(&self, py: Python) -> PyResult<usize> {
+        self.with_content_hierarchy(py, |content| content.level)
+    }
+
+    #[setter]
+    fn set_level(&mut self, py: Python, level: usize) -> PyResult<()> {
+        self.with_content_hierarchy_mut(py, |content| content.level = level)
+    }
+
+    #[getter]
+    fn heading(&self, py: Python) -> PyResult<Py<FrozenRichText>> {
+        let heading = self.with_content_hierarchy(py, |content| content.heading.clone())?;
+        FrozenRichText::new_py(py, heading)
+    }
+
+    #[setter]
+    fn set_heading(&mut self, py: Python, heading: PyRef<RichText>) -> PyResult<()> {
+        self.with_content_hierarchy_mut(py, |content| content.heading = heading.clone())
+    }
+
+    #[getter]
+    fn content_before(&self, py: Python) -> PyResult<Vec<Py<FrozenRichText>>> {
+        self.with_content_hierarchy(py, |content| content.content_before.clone())?
+            .into_iter()
+            .map(|text| FrozenRichText::new_py(py, text))
+            .collect()
+    }
+
+    #[getter]
+    fn content_after(&self, py: Python) -> PyResult<Vec<Py<FrozenRichText>>> {
+        self.with_content_hierarchy(py, |content| content.content_after.clone())?
+            .into_iter()
+            .map(|text| FrozenRichText::new_py(py, text))
+            .collect()
+    }
+
+    #[pyo3(name = "to_dict")]
+    pub fn py_to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+        self.with_content_hierarchy(py, |content| content.to_dict(py))?
+    }
 
     #[staticmethod]
-    pub fn from_dict(py: Python, obj: &PyDict) -> PyResult<Self> {
+    pub fn from_dict(obj: &PyDict) -> PyResult<Self> {
         let level = obj
             .get_item("level")
             .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("level"))?
             .extract::<usize>()?;
 
-        let heading = Py::new(
-            py,
-            RichText::from_dict(
-                obj.get_item("heading")
-                    .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("heading"))?
-                    .downcast::<PyDict>()?,
-            )?,
+        let heading = RichText::from_dict(
+            obj.get_item("heading")
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("heading"))?
+                .downcast::<PyDict>()?,
         )?;
 
         let content_before = obj
@@ -85,7 +227,7 @@ impl ContentHierarchy {
             .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("content_before"))?
             .downcast::<PyList>()?
             .iter()
-            .map(|o| Py::new(py, RichText::from_dict(o.downcast::<PyDict>()?)?))
+            .map(|o| RichText::from_dict(o.downcast::<PyDict>()?))
             .collect::<PyResult<Vec<_>>>()?;
 
         let content_after = obj
@@ -93,51 +235,48 @@ impl ContentHierarchy {
             .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>("content_after"))?
             .downcast::<PyList>()?
             .iter()
-            .map(|o| Py::new(py, RichText::from_dict(o.downcast::<PyDict>()?)?))
+            .map(|o| RichText::from_dict(o.downcast::<PyDict>()?))
             .collect::<PyResult<Vec<_>>>()?;
 
-        Ok(ContentHierarchy {
+        Ok(PyContentHierarchy::from(ContentHierarchy {
             level,
             heading,
             content_before,
             content_after,
-        })
+        }))
     }
 
-    pub fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
-        let out = to_allocvec(&self).map_err(into_pyerr)?;
+    pub fn to_json(&self, py: Python) -> PyResult<String> {
+        self.with_content_hierarchy(py, |content| content.to_json())?
+            .map_err(into_pyerr)
+    }
+
+    #[staticmethod]
+    pub fn from_json(dat: &str) -> PyResult<Self> {
+        let content = ContentHierarchy::from_json(dat).map_err(into_pyerr)?;
+        Ok(PyContentHierarchy::from(content))
+    }
+
+    pub fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let out = self
+            .with_content_hierarchy(py, |content| content.to_bytes())?
+            .map_err(into_pyerr)?;
         Ok(PyBytes::new(py, &out))
     }
 
-    pub fn __setstate__(&mut self, state: &PyBytes) -> PyResult<()> {
-        *self = from_bytes::<ContentHierarchy>(state.as_bytes()).map_err(into_pyerr)?;
-        Ok(())
+    #[staticmethod]
+    pub fn from_bytes(dat: &PyBytes) -> PyResult<Self> {
+        let content = ContentHierarchy::from_bytes(dat.as_bytes()).map_err(into_pyerr)?;
+        Ok(PyContentHierarchy::from(content))
+    }
+
+    pub fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        self.to_bytes(py)
     }
-}
 
-impl fmt::Debug for ContentHierarchy {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        Python::with_gil(|py| {
-            f.debug_struct("ContentHierarchy")
-                .field("level", &self.level)
-                .field("heading", &self.heading.borrow(py))
-                .field(
-                    "content_before",
-                    &self
-                        .content_before
-                        .iter()
-                        .map(|l| l.borrow(py))
-                        .collect::<Vec<_>>(),
-                )
-                .field(
-                    "content_after",
-                    &self
-                        .content_after
-                        .iter()
-                        .map(|l| l.borrow(py))
-                        .collect::<Vec<_>>(),
-                )
-                .finish()
-        })
+    pub fn __setstate__(&mut self, state: &PyBytes) -> PyResult<()> {
+        let content = ContentHierarchy::from_bytes(state.as_bytes()).map_err(into_pyerr)?;
+        self.data = ContentHierarchyData::Owned(content);
+        Ok(())
     }
 }
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+/// Reason why a `<table>` element did not yield a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SkipReason {
+    // the table contains other tables
+    NestedTable,
+    // the table has no rows or all of its rows are empty
+    NoCells,
+    // the table's rowspan/colspan cannot be resolved
+    SpanError,
+    // the table is nested in a table that is skipped, or outside of the cells of its parent table
+    ParentSkipped,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::NestedTable => "nested_table",
+            SkipReason::NoCells => "no_cells",
+            SkipReason::SpanError => "span_error",
+            SkipReason::ParentSkipped => "parent_skipped",
+        }
+    }
+}
+
+/// What happened to a single `<table>` element during extraction
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
+pub struct TableReport {
+    // position of the `<table>` element among all `<table>` elements of the document
+    pub index: usize,
+    // id of the table, only available if the table has cells
+    pub table_id: Option<String>,
+    // none if the table is returned
+    pub skip_reason: Option<SkipReason>,
+    pub errors: Vec<String>,
+}
+
+/// Report of a table extraction, containing one entry per `<table>` element in document order
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
+pub struct ExtractionReport {
+    pub tables: Vec<TableReport>,
+}
+
+impl ExtractionReport {
+    pub fn n_extracted(&self) -> usize {
+        self.tables
+            .iter()
+            .filter(|t| t.skip_reason.is_none())
+            .count()
+    }
+
+    pub fn n_skipped(&self) -> usize {
+        self.tables.len() - self.n_extracted()
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl TableReport {
+    #[getter]
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    #[getter]
+    fn table_id(&self) -> Option<String> {
+        self.table_id.clone()
+    }
+
+    #[getter]
+    fn skip_reason(&self) -> Option<&'static str> {
+        self.skip_reason.as_ref().map(SkipReason::as_str)
+    }
+
+    #[getter]
+    fn errors(&self) -> Vec<String> {
+        self.errors.clone()
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ExtractionReport {
+    #[getter]
+    fn tables(&self) -> Vec<TableReport> {
+        self.tables.clone()
+    }
+
+    #[pyo3(name = "n_extracted")]
+    fn py_n_extracted(&self) -> usize {
+        self.n_extracted()
+    }
+
+    #[pyo3(name = "n_skipped")]
+    fn py_n_skipped(&self) -> usize {
+        self.n_skipped()
+    }
+}
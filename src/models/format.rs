@@ -0,0 +1,139 @@
+//! Versioned binary format of the models.
+//!
+//! Data is encoded with postcard and prefixed by a header: the magic bytes `\xffRS`, a byte
+//! identifying the kind of data, and the version of its layout as a little-endian u16. The
+//! version of a kind is bumped whenever its layout (or the layout of a type it contains)
+//! changes, and the decoder of the kind migrates data of older versions.
+//!
+//! Data written before the header existed has no header, it is decoded as version 1.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::RSoupError;
+
+pub const MAGIC: [u8; 3] = [0xff, b'R', b'S'];
+pub const HEADER_SIZE: usize = MAGIC.len() + 3;
+
+/// Kind of data encoded in the binary format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataKind {
+    Table,
+    Row,
+    Cell,
+    RichText,
+    RichTextElement,
+    ContentHierarchy,
+}
+
+impl DataKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataKind::Table => "Table",
+            DataKind::Row => "Row",
+            DataKind::Cell => "Cell",
+            DataKind::RichText => "RichText",
+            DataKind::RichTextElement => "RichTextElement",
+            DataKind::ContentHierarchy => "ContentHierarchy",
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            DataKind::Table => b'T',
+            DataKind::Row => b'W',
+            DataKind::Cell => b'L',
+            DataKind::RichText => b'R',
+            DataKind::RichTextElement => b'E',
+            DataKind::ContentHierarchy => b'C',
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<DataKind> {
+        match tag {
+            b'T' => Some(DataKind::Table),
+            b'W' => Some(DataKind::Row),
+            b'L' => Some(DataKind::Cell),
+            b'R' => Some(DataKind::RichText),
+            b'E' => Some(DataKind::RichTextElement),
+            b'C' => Some(DataKind::ContentHierarchy),
+            _ => None,
+        }
+    }
+
+    /// Version of the layout written by this version of the library
+    ///
+    /// * Table 2: rows have a section, cells have nested tables and provenance after spanning,
+    ///   tables have a parent id, column descriptors and header
+    /// * Row 1 and Cell 1: same layouts as in Table 2, as rows and cells are not encoded on
+    ///   their own before
+    /// * Table 3, Row 2 and Cell 2: rows have the index of their row group
+    pub fn current_version(&self) -> u16 {
+        match self {
+            DataKind::Table => 3,
+            DataKind::Row => 2,
+            DataKind::Cell => 2,
+            DataKind::RichText => 1,
+            DataKind::RichTextElement => 1,
+            DataKind::ContentHierarchy => 1,
+        }
+    }
+}
+
+/// Encode a value with the header of the current version of its kind
+pub fn encode<T: Serialize>(kind: DataKind, value: &T) -> Result<Vec<u8>, RSoupError> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC);
+    out.push(kind.tag());
+    out.extend_from_slice(&kind.current_version().to_le_bytes());
+    out.extend_from_slice(&postcard::to_allocvec(value)?);
+    Ok(out)
+}
+
+/// Read the header of encoded data, return the version of the layout and the encoded value.
+///
+/// Returns an error if the data is of another kind or of a version newer than the one
+/// supported by this library.
+pub fn decode_header(kind: DataKind, bytes: &[u8]) -> Result<(u16, &[u8]), RSoupError> {
+    if bytes.len() < HEADER_SIZE || bytes[..MAGIC.len()] != MAGIC {
+        return Ok((1, bytes));
+    }
+
+    let tag = bytes[MAGIC.len()];
+    let version = u16::from_le_bytes([bytes[MAGIC.len() + 1], bytes[MAGIC.len() + 2]]);
+    if tag != kind.tag() {
+        let found = DataKind::from_tag(tag).map_or("unknown", |k| k.as_str());
+        return Err(RSoupError::InvalidFormatError {
+            kind: kind.as_str(),
+            version,
+            reason: format!("the data is of kind {}", found),
+        });
+    }
+
+    if version == 0 || version > kind.current_version() {
+        return Err(RSoupError::UnsupportedFormatVersionError {
+            kind: kind.as_str(),
+            version,
+            max_version: kind.current_version(),
+        });
+    }
+    Ok((version, &bytes[HEADER_SIZE..]))
+}
+
+/// Decode a value of a given version with postcard, reporting failures as incompatible data
+pub fn decode_postcard<'a, T: Deserialize<'a>>(
+    kind: DataKind,
+    version: u16,
+    bytes: &'a [u8],
+) -> Result<T, RSoupError> {
+    postcard::from_bytes(bytes).map_err(|err| RSoupError::InvalidFormatError {
+        kind: kind.as_str(),
+        version,
+        reason: err.to_string(),
+    })
+}
+
+/// Decode data of a kind whose layout has not changed since version 1
+pub fn decode<'a, T: Deserialize<'a>>(kind: DataKind, bytes: &'a [u8]) -> Result<T, RSoupError> {
+    let (version, payload) = decode_header(kind, bytes)?;
+    decode_postcard(kind, version, payload)
+}
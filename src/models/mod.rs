@@ -1,3 +1,6 @@
 pub mod content_hierarchy;
+pub mod extraction_report;
+pub mod format;
 pub mod rich_text;
+pub mod selector;
 pub mod table;
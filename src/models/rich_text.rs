@@ -1,21 +1,37 @@
 use hashbrown::HashMap;
-use pyo3::exceptions::PyKeyError;
-use pyo3::types::PyBytes;
-use std::fmt;
+use phf::{phf_set, Set};
+use std::{fmt, ops::Range, str::FromStr};
 
-use crate::misc::tree::iterator::ITree;
+use crate::extractors::text::BLOCK_ELEMENTS;
+use crate::misc::range_iter::RangeIter;
 use crate::misc::tree::simple_tree::SimpleTree;
-use crate::{error::into_pyerr, misc::range_iter::RangeIter};
-use postcard::{from_bytes, to_allocvec};
-use pyo3::{prelude::*, types::PyDict, types::PyList};
+use crate::misc::{escape_html, escape_html_text, escape_markdown};
 use serde::{Deserialize, Serialize};
 
+use crate::error::RSoupError;
+use crate::models::format::{self, DataKind};
+use crate::models::selector::RichTextSelector;
+
+#[cfg(feature = "python")]
+use crate::error::into_pyerr;
+#[cfg(feature = "python")]
+use pyo3::{
+    exceptions::{PyKeyError, PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict, PyList, PyTuple},
+};
+
 pub const PSEUDO_TAG: &str = "";
 
-#[pyclass(module = "rsoup.core")]
+/// Elements that cannot have content and are written without an end tag
+static VOID_ELEMENTS: Set<&'static str> = phf_set! {
+    "area", "base", "br", "col", "embed", "hr", "img", "input",
+    "link", "meta", "param", "source", "track", "wbr",
+};
+
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core", subclass))]
 #[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct RichText {
-    #[pyo3(get)]
     pub text: String,
     // html elements creating this text, the root of the tree
     // is a pseudo-element, most often, it will be the html element containing
@@ -25,19 +41,64 @@ pub struct RichText {
 }
 
 /// Represent an html element.
-#[pyclass(module = "rsoup.core")]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct RichTextElement {
-    #[pyo3(get)]
     pub tag: String,
-    #[pyo3(get)]
     pub start: usize,
-    #[pyo3(get)]
     pub end: usize,
-    #[pyo3(get)]
     pub attrs: HashMap<String, String>,
 }
 
+/// Unit of offsets in a text. Offsets of elements are stored in bytes of the UTF-8 text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OffsetUnit {
+    #[default]
+    Byte,
+    /// Unicode code points, the unit of indices of Python strings
+    CodePoint,
+    /// UTF-16 code units, the unit of indices of JavaScript strings
+    Utf16,
+}
+
+impl OffsetUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OffsetUnit::Byte => "byte",
+            OffsetUnit::CodePoint => "codepoint",
+            OffsetUnit::Utf16 => "utf16",
+        }
+    }
+
+    /// Length of a character in this unit
+    fn char_len(&self, c: char) -> usize {
+        match self {
+            OffsetUnit::Byte => c.len_utf8(),
+            OffsetUnit::CodePoint => 1,
+            OffsetUnit::Utf16 => c.len_utf16(),
+        }
+    }
+}
+
+impl FromStr for OffsetUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "byte" => Ok(OffsetUnit::Byte),
+            "codepoint" => Ok(OffsetUnit::CodePoint),
+            "utf16" => Ok(OffsetUnit::Utf16),
+            _ => Err(format!("Invalid offset unit: {}", s)),
+        }
+    }
+}
+
+impl Default for RichText {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RichText {
     // Create an empty rich text, you should not use this function directly to
     // build a rich text as the tree has the PSEUDO_TAG.
@@ -81,11 +142,8 @@ impl RichText {
         }
         is_valid
     }
-}
 
-#[pymethods]
-impl RichText {
-    #[staticmethod]
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(text: &str) -> RichText {
         RichText {
             text: text.to_owned(),
@@ -102,6 +160,10 @@ impl RichText {
         self.text.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
     pub fn iter_element_id(&self) -> RangeIter {
         RangeIter {
             start: 0,
@@ -109,13 +171,6 @@ impl RichText {
         }
     }
 
-    pub fn iter_element_id_preorder(
-        slf: Py<RichText>,
-        py: Python,
-    ) -> RichTextElementIdPreorderIter {
-        RichTextElementIdPreorderIter::new(slf.clone_ref(py))
-    }
-
     pub fn get_element_tag_by_id(&self, id: usize) -> String {
         self.element.get_node(id).tag.clone()
     }
@@ -143,100 +198,769 @@ impl RichText {
             .map(ToOwned::to_owned)
     }
 
-    #[args("*", render_outer_element = "true", render_element_attrs = "false")]
+    /// Render the text as HTML. The text and the attribute values are escaped, attributes are
+    /// written in alphabetical order, and void elements (e.g., `br`) have no end tag.
     pub fn to_html(&self, render_outer_element: bool, render_element_attrs: bool) -> String {
-        let mut tokens = Vec::<&str>::with_capacity(2 + self.element.len());
-        // keep track of pending tags that need to be closed
-        let mut closing_tag_ids = Vec::<usize>::new();
-        let mut pointer = 0;
-        let mut it = self.element.iter_id_preorder();
-        let mut string_pools = Vec::new();
-        let mut pending_ops = Vec::new();
-
-        if !render_outer_element {
-            it.next();
-        }
-
-        for token_id in it {
-            let token = self.element.get_node(*token_id);
-            // println!(
-            //     "------before\n\t>> pointer: {}\n\t>> token: {:?}\n\t>> tokens: {:?}\n\t>> closing_tags: {:?}",
-            //     pointer, token, tokens, closing_tag_ids.iter().map(|id| self.element.get_node(*id)).collect::<Vec<_>>()
-            // );
-
-            while let Some(closing_tag_id) = closing_tag_ids.last() {
-                let closing_tag = self.element.get_node(*closing_tag_id);
-
-                if closing_tag.end <= token.start {
-                    // this tag is closed
-                    if token.start == token.end {
-                        // this token is empty, but is it part of this closing tag?
-                        // if not, we can continue to closing tag
-                        // if yes, we break here.
-                        // and it only happens when its a direct children
-                        if self
-                            .element
-                            .get_child_ids_ref(closing_tag_id)
-                            .iter()
-                            .any(|child_id| child_id == token_id)
-                        {
-                            break;
-                        }
-                    }
-                    tokens.push(&self.text[pointer..closing_tag.end]);
-                    tokens.push("</");
-                    tokens.push(&closing_tag.tag);
-                    tokens.push(">");
-                    pointer = closing_tag.end;
-                    closing_tag_ids.pop();
-                } else {
-                    break;
+        let allowed_attrs: Option<&[&str]> = if render_element_attrs {
+            None
+        } else {
+            Some(&[])
+        };
+        self.to_html_with_attrs(render_outer_element, allowed_attrs)
+    }
+
+    /// Render the text as HTML like [`RichText::to_html`], with only the attributes in
+    /// `allowed_attrs`, or all attributes if it is None.
+    pub fn to_html_with_attrs(
+        &self,
+        render_outer_element: bool,
+        allowed_attrs: Option<&[&str]>,
+    ) -> String {
+        let mut out = String::with_capacity(self.text.len() * 2);
+        let root_id = self.element.get_root_id();
+        if render_outer_element {
+            self.write_html_element(&mut out, root_id, allowed_attrs);
+        } else {
+            self.write_html_content(&mut out, root_id, allowed_attrs);
+        }
+        out
+    }
+
+    fn write_html_element(&self, out: &mut String, id: usize, allowed_attrs: Option<&[&str]>) {
+        let node = self.element.get_node(id);
+        out.push('<');
+        out.push_str(&node.tag);
+        let mut attrs = node
+            .attrs
+            .iter()
+            .filter(|(name, _)| {
+                allowed_attrs.is_none_or(|allowed| allowed.contains(&name.as_str()))
+            })
+            .collect::<Vec<_>>();
+        attrs.sort();
+        for (name, value) in attrs {
+            out.push(' ');
+            out.push_str(name);
+            out.push_str("=\"");
+            out.push_str(&escape_html(value));
+            out.push('"');
+        }
+        out.push('>');
+
+        if VOID_ELEMENTS.contains(node.tag.as_str()) && node.start == node.end {
+            return;
+        }
+        self.write_html_content(out, id, allowed_attrs);
+        out.push_str("</");
+        out.push_str(&node.tag);
+        out.push('>');
+    }
+
+    /// Write the text and the descendants of an element
+    fn write_html_content(&self, out: &mut String, id: usize, allowed_attrs: Option<&[&str]>) {
+        let node = self.element.get_node(id);
+        let mut pointer = node.start;
+        for &child_id in self.element.get_child_ids(id) {
+            let child = self.element.get_node(child_id);
+            out.push_str(&escape_html_text(&self.text[pointer..child.start]));
+            self.write_html_element(out, child_id, allowed_attrs);
+            pointer = child.end;
+        }
+        out.push_str(&escape_html_text(&self.text[pointer..node.end]));
+    }
+
+    /// Encode in the versioned binary format, see [`crate::models::format`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RSoupError> {
+        format::encode(DataKind::RichText, self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<RichText, RSoupError> {
+        format::decode(DataKind::RichText, bytes)
+    }
+
+    pub fn to_json(&self) -> Result<String, RSoupError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(dat: &str) -> Result<RichText, RSoupError> {
+        Ok(serde_json::from_str(dat)?)
+    }
+
+    /// Render the text as plain text with a footnote marker `[n]` after each link (`a` elements
+    /// with `href`), and the hrefs listed as `[n]: href` at the end. Links to the same href share
+    /// the same footnote.
+    pub fn to_text_with_links(&self) -> String {
+        let mut hrefs: Vec<&str> = Vec::new();
+        let mut out = self.render_links(|out, text, href| {
+            let index = match hrefs.iter().position(|&h| h == href) {
+                Some(index) => index,
+                None => {
+                    hrefs.push(href);
+                    hrefs.len() - 1
                 }
+            };
+            out.push_str(text);
+            out.push_str(&format!("[{}]", index + 1));
+        });
+        if !hrefs.is_empty() {
+            out.push('\n');
+            for (index, href) in hrefs.iter().enumerate() {
+                out.push_str(&format!("\n[{}]: {}", index + 1, href));
+            }
+        }
+        out
+    }
+
+    /// Render the text, letting `render_link` write the text and href of each outermost link
+    /// (`a` elements with `href`)
+    fn render_links<'s, F: FnMut(&mut String, &'s str, &'s str)>(
+        &'s self,
+        mut render_link: F,
+    ) -> String {
+        let mut out = String::with_capacity(self.text.len());
+        let mut pointer = 0;
+
+        for node_id in self.element.iter_id_preorder() {
+            let node = self.element.get_node(*node_id);
+            // skip links inside a link that has been rendered
+            if node.tag != "a" || node.start < pointer {
+                continue;
+            }
+            let href = match node.attrs.get("href") {
+                None => continue,
+                Some(href) => href,
+            };
+
+            out.push_str(&self.text[pointer..node.start]);
+            render_link(&mut out, &self.text[node.start..node.end], href);
+            pointer = node.end;
+        }
+        out.push_str(&self.text[pointer..]);
+        out
+    }
+
+    /// Render the text as markdown: links (`a` with `href`) as `[text](href)`, `b`/`strong` as
+    /// `**text**`, `i`/`em` as `_text_`, `code` as code spans, and `sup`/`sub` as inline HTML.
+    /// Line breaks of the text, `br` and the boundaries of block elements are written as hard
+    /// line breaks (`\` followed by a newline). Markdown markup characters of the text are escaped.
+    pub fn to_markdown(&self) -> String {
+        let mut writer = MarkdownWriter {
+            text: self,
+            out: String::with_capacity(self.text.len() + self.text.len() / 4),
+            pending_break: false,
+        };
+        writer.write_content(self.element.get_root_id(), false);
+        writer.out
+    }
+
+    /// Convert a byte offset of the text into an offset in the unit
+    pub fn from_byte_offset(&self, offset: usize, unit: OffsetUnit) -> Result<usize, RSoupError> {
+        if offset > self.len() || !self.text.is_char_boundary(offset) {
+            return Err(RSoupError::InvalidTextOffsetError {
+                offset,
+                unit: OffsetUnit::Byte.as_str(),
+            });
+        }
+        Ok(match unit {
+            OffsetUnit::Byte => offset,
+            _ => self.text[..offset].chars().map(|c| unit.char_len(c)).sum(),
+        })
+    }
+
+    /// Convert an offset in the unit into a byte offset of the text, fails if the offset is
+    /// out of the text or in the middle of a character (e.g., of a UTF-16 surrogate pair)
+    pub fn to_byte_offset(&self, offset: usize, unit: OffsetUnit) -> Result<usize, RSoupError> {
+        if unit == OffsetUnit::Byte {
+            return self.from_byte_offset(offset, unit);
+        }
+        let mut current = 0;
+        for (i, c) in self.text.char_indices() {
+            if current == offset {
+                return Ok(i);
             }
+            if current > offset {
+                break;
+            }
+            current += unit.char_len(c);
+        }
+        if current == offset {
+            return Ok(self.len());
+        }
+        Err(RSoupError::InvalidTextOffsetError {
+            offset,
+            unit: unit.as_str(),
+        })
+    }
+
+    /// Convert an offset of the text from one unit to another
+    pub fn convert_offset(
+        &self,
+        offset: usize,
+        from_unit: OffsetUnit,
+        to_unit: OffsetUnit,
+    ) -> Result<usize, RSoupError> {
+        self.from_byte_offset(self.to_byte_offset(offset, from_unit)?, to_unit)
+    }
 
-            tokens.push(&self.text[pointer..token.start]);
-            tokens.push("<");
-            tokens.push(&token.tag);
-            if render_element_attrs {
-                for (name, value) in token.attrs.iter() {
-                    tokens.push(" ");
-                    tokens.push(name);
-                    tokens.push("=\"");
-                    string_pools.push(value.replace("\"", "\\\""));
-                    pending_ops.push(tokens.len());
-                    tokens.push("");
-                    tokens.push("\"");
+    /// Ids of the elements matching the selector, in preorder. The outer element is included
+    /// if it matches and is not a pseudo-element.
+    pub fn select(&self, selector: &RichTextSelector) -> Vec<usize> {
+        let mut ids = Vec::new();
+        let mut path = Vec::new();
+        // (element id, depth)
+        let mut stack = vec![(self.element.get_root_id(), 0)];
+        while let Some((id, depth)) = stack.pop() {
+            path.truncate(depth);
+            path.push(id);
+            if self.element.get_node(id).tag != PSEUDO_TAG && selector.matches(self, &path) {
+                ids.push(id);
+            }
+            stack.extend(
+                self.element
+                    .get_child_ids(id)
+                    .iter()
+                    .rev()
+                    .map(|&child_id| (child_id, depth + 1)),
+            );
+        }
+        ids
+    }
+
+    /// Ids of the elements whose text contains the byte at the offset, from the outer element
+    /// to the innermost one. Empty elements never contain an offset.
+    pub fn elements_at(&self, offset: usize) -> Vec<usize> {
+        let mut ids = Vec::new();
+        let mut id = self.element.get_root_id();
+        loop {
+            let node = self.element.get_node(id);
+            if offset < node.start || offset >= node.end {
+                return ids;
+            }
+            ids.push(id);
+            match self.element.get_child_ids(id).iter().find(|&&child_id| {
+                let child = self.element.get_node(child_id);
+                child.start <= offset && offset < child.end
+            }) {
+                None => return ids,
+                Some(&child_id) => id = child_id,
+            }
+        }
+    }
+
+    /// Remove the elements matching the selector, together with their text and descendants,
+    /// and shift the offsets of the remaining elements. The outer element is never removed.
+    pub fn remove_elements(&self, selector: &RichTextSelector) -> RichText {
+        self.remove_elements_where(|path| selector.matches(self, path))
+    }
+
+    /// Remove the elements for which the predicate is true, see [`RichText::remove_elements`]
+    pub fn remove_elements_by<F: FnMut(&RichTextElement) -> bool>(
+        &self,
+        mut predicate: F,
+    ) -> RichText {
+        self.remove_elements_where(|path| predicate(self.element.get_node(path[path.len() - 1])))
+    }
+
+    /// Remove the elements for which the predicate is true, the predicate receives the ids of
+    /// the element and of its ancestors, starting from the root.
+    fn remove_elements_where<F: FnMut(&[usize]) -> bool>(&self, mut predicate: F) -> RichText {
+        let root_id = self.element.get_root_id();
+        let mut element = SimpleTree::new(self.element.get_root().clone());
+        // ranges of the removed text, in order
+        let mut removed = Vec::new();
+        let mut path = vec![root_id];
+        // (element id, depth, id of the parent in the new tree)
+        let mut stack = self
+            .element
+            .get_child_ids(root_id)
+            .iter()
+            .rev()
+            .map(|&id| (id, 1, element.get_root_id()))
+            .collect::<Vec<_>>();
+
+        while let Some((id, depth, parent_id)) = stack.pop() {
+            path.truncate(depth);
+            path.push(id);
+            let node = self.element.get_node(id);
+            if predicate(&path) {
+                removed.push((node.start, node.end));
+                continue;
+            }
+            let new_id = element.add_node(node.clone());
+            element.add_child(parent_id, new_id);
+            stack.extend(
+                self.element
+                    .get_child_ids(id)
+                    .iter()
+                    .rev()
+                    .map(|&child_id| (child_id, depth + 1, new_id)),
+            );
+        }
+
+        if removed.is_empty() {
+            return self.clone();
+        }
+
+        let mut text = String::with_capacity(self.text.len());
+        let mut pointer = 0;
+        for &(start, end) in &removed {
+            text.push_str(&self.text[pointer..start]);
+            pointer = end;
+        }
+        text.push_str(&self.text[pointer..]);
+
+        // elements do not overlap the removed ranges partially, so an offset is shifted by
+        // the length of the removed ranges before it
+        let shift = |offset: usize| {
+            offset
+                - removed
+                    .iter()
+                    .take_while(|(_, end)| *end <= offset)
+                    .map(|(start, end)| end - start)
+                    .sum::<usize>()
+        };
+        for node in element.iter_mut() {
+            node.start = shift(node.start);
+            node.end = shift(node.end);
+        }
+        RichText { text, element }
+    }
+
+    /// Part of the text in the byte range, with the elements overlapping the range clipped to
+    /// it. Empty elements (e.g., `br`) are kept if they are at the start of or inside the range,
+    /// or at the end of the text. The outer element is always kept.
+    pub fn slice(&self, start: usize, end: usize) -> Result<RichText, RSoupError> {
+        self.check_range(start, end)?;
+        let len = self.len();
+        Ok(self.rebuild(self.text[start..end].to_owned(), |el| {
+            let is_kept = if el.start == el.end {
+                (start <= el.start && el.start < end) || el.start == len
+            } else {
+                el.start < end && el.end > start
+            };
+            is_kept.then(|| {
+                (
+                    el.start.clamp(start, end) - start,
+                    el.end.clamp(start, end) - start,
+                )
+            })
+        }))
+    }
+
+    /// Concatenate two rich texts. The outer element of the result is a pseudo-element
+    /// containing the elements of both texts, outer elements that are not pseudo-elements
+    /// included.
+    pub fn concat(&self, other: &RichText) -> RichText {
+        let mut text = RichText::from_str(&(self.text.clone() + &other.text));
+        let root_id = text.element.get_root_id();
+        for (part, offset) in [(self, 0), (other, self.len())] {
+            let mut subtree = part.element.clone();
+            for node in subtree.iter_mut() {
+                node.start += offset;
+                node.end += offset;
+            }
+            if part.get_tag() == PSEUDO_TAG {
+                text.element.merge_subtree_no_root(root_id, subtree);
+            } else {
+                text.element.merge_subtree(root_id, subtree);
+            }
+        }
+        text
+    }
+
+    /// Replace the text in the byte range with a string. Elements containing the range
+    /// contain the new string, elements inside the range are removed, and elements partially
+    /// overlapping the range are clipped to the remaining text.
+    pub fn replace(&self, range: Range<usize>, value: &str) -> Result<RichText, RSoupError> {
+        let (start, end) = (range.start, range.end);
+        self.check_range(start, end)?;
+        let new_end = start + value.len();
+        let text = format!("{}{}{}", &self.text[..start], value, &self.text[end..]);
+        let shift = |offset: usize| offset + new_end - end;
+
+        Ok(self.rebuild(text, |el| {
+            if el.end <= start {
+                Some((el.start, el.end))
+            } else if el.start >= end {
+                Some((shift(el.start), shift(el.end)))
+            } else if el.start <= start && el.end >= end {
+                Some((el.start, shift(el.end)))
+            } else if start <= el.start && el.end <= end {
+                None
+            } else if el.start < start {
+                Some((el.start, start))
+            } else {
+                Some((new_end, shift(el.end)))
+            }
+        }))
+    }
+
+    /// Insert a string at the byte offset, it belongs to the elements strictly containing the
+    /// offset, see [`RichText::replace`]
+    pub fn insert(&self, offset: usize, value: &str) -> Result<RichText, RSoupError> {
+        self.replace(offset..offset, value)
+    }
+
+    fn check_range(&self, start: usize, end: usize) -> Result<(), RSoupError> {
+        if start > end
+            || end > self.len()
+            || !self.text.is_char_boundary(start)
+            || !self.text.is_char_boundary(end)
+        {
+            return Err(RSoupError::InvalidTextRangeError {
+                start,
+                end,
+                len: self.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Build a rich text of a new text, with the elements of this text at the offsets returned
+    /// by `update`, or without them if it returns None (their remaining descendants are moved
+    /// to their closest remaining ancestor). The outer element always spans the new text.
+    fn rebuild<F: FnMut(&RichTextElement) -> Option<(usize, usize)>>(
+        &self,
+        text: String,
+        mut update: F,
+    ) -> RichText {
+        let root_id = self.element.get_root_id();
+        let mut root = self.element.get_root().clone();
+        root.start = 0;
+        root.end = text.len();
+        let mut element = SimpleTree::new(root);
+
+        // (element id, id of the parent in the new tree)
+        let mut stack = self
+            .element
+            .get_child_ids(root_id)
+            .iter()
+            .rev()
+            .map(|&id| (id, element.get_root_id()))
+            .collect::<Vec<_>>();
+        while let Some((id, parent_id)) = stack.pop() {
+            let node = self.element.get_node(id);
+            let new_parent_id = match update(node) {
+                None => parent_id,
+                Some((start, end)) => {
+                    let new_id = element.add_node(RichTextElement {
+                        tag: node.tag.clone(),
+                        start,
+                        end,
+                        attrs: node.attrs.clone(),
+                    });
+                    element.add_child(parent_id, new_id);
+                    new_id
                 }
+            };
+            stack.extend(
+                self.element
+                    .get_child_ids(id)
+                    .iter()
+                    .rev()
+                    .map(|&child_id| (child_id, new_parent_id)),
+            );
+        }
+        RichText { text, element }
+    }
+
+    pub fn new() -> Self {
+        RichText::empty()
+    }
+}
+
+/// Write a [`RichText`] as markdown. Line breaks are delayed until there is text after them,
+/// so that consecutive breaks collapse and no break is written at the start or the end.
+struct MarkdownWriter<'t> {
+    text: &'t RichText,
+    out: String,
+    pending_break: bool,
+}
+
+impl<'t> MarkdownWriter<'t> {
+    const LINE_BREAK: &'static str = "\\\n";
+
+    fn write_element(&mut self, id: usize, in_link: bool) {
+        let node = self.text.element.get_node(id);
+        match node.tag.as_str() {
+            "a" if !in_link && node.attrs.contains_key("href") => {
+                self.flush_break();
+                self.out.push('[');
+                self.write_content(id, true);
+                self.out.push_str("](");
+                push_markdown_url(&mut self.out, &node.attrs["href"]);
+                self.out.push(')');
             }
-            tokens.push(">");
+            "b" | "strong" => self.write_delimited(id, in_link, "**", "**"),
+            "i" | "em" => self.write_delimited(id, in_link, "_", "_"),
+            "sup" => self.write_delimited(id, in_link, "<sup>", "</sup>"),
+            "sub" => self.write_delimited(id, in_link, "<sub>", "</sub>"),
+            "code" => self.write_code(&self.text.text[node.start..node.end]),
+            tag if BLOCK_ELEMENTS.contains(tag) => {
+                self.pending_break = true;
+                self.write_content(id, in_link);
+                self.pending_break = true;
+            }
+            _ => self.write_content(id, in_link),
+        }
+    }
 
-            pointer = token.start;
-            closing_tag_ids.push(*token_id);
+    fn write_content(&mut self, id: usize, in_link: bool) {
+        let node = self.text.element.get_node(id);
+        let mut pointer = node.start;
+        for &child_id in self.text.element.get_child_ids(id) {
+            let child = self.text.element.get_node(child_id);
+            self.write_text(&self.text.text[pointer..child.start]);
+            self.write_element(child_id, in_link);
+            pointer = child.end;
+        }
+        self.write_text(&self.text.text[pointer..node.end]);
+    }
+
+    /// Write the content of an element between two delimiters. Leading and trailing whitespace
+    /// and line breaks are moved out of the delimiters, and nothing is written if it is empty.
+    fn write_delimited(&mut self, id: usize, in_link: bool, open: &str, close: &str) {
+        let start = self.out.len();
+        self.write_content(id, in_link);
+        let content = self.out.split_off(start);
 
-            // println!(
-            //     "------after\n\t>> pointer: {}\n\t>> token: {:?}\n\t>> tokens: {:?}\n\t>> closing_tags: {:?}",
-            //     pointer, token, tokens, closing_tag_ids.iter().map(|id| self.element.get_node(*id)).collect::<Vec<_>>()
-            // );
+        let mut inner = content.as_str();
+        loop {
+            let trimmed = inner.trim_start();
+            inner = trimmed.strip_prefix(Self::LINE_BREAK).unwrap_or(trimmed);
+            if inner.len() == trimmed.len() {
+                break;
+            }
+        }
+        let lead = &content[..content.len() - inner.len()];
+        loop {
+            let trimmed = inner.trim_end();
+            inner = trimmed.strip_suffix(Self::LINE_BREAK).unwrap_or(trimmed);
+            if inner.len() == trimmed.len() {
+                break;
+            }
         }
+        let trail = &content[lead.len() + inner.len()..];
 
-        for closing_tag_id in closing_tag_ids.iter().rev() {
-            let closing_tag = self.element.get_node(*closing_tag_id);
-            tokens.push(&self.text[pointer..closing_tag.end]);
-            tokens.push("</");
-            tokens.push(&closing_tag.tag);
-            tokens.push(">");
-            pointer = closing_tag.end;
+        self.out.push_str(lead);
+        if !inner.is_empty() {
+            self.out.push_str(open);
+            self.out.push_str(inner);
+            self.out.push_str(close);
         }
-        tokens.push(&self.text[pointer..]);
+        self.out.push_str(trail);
+    }
 
-        // update tokens
-        for (i, j) in pending_ops.into_iter().enumerate() {
-            tokens[j] = &string_pools[i];
+    /// Write a code span, delimited by a backtick string longer than any backtick string of the code
+    fn write_code(&mut self, code: &str) {
+        let code = code.replace('\n', " ");
+        if code.trim().is_empty() {
+            self.write_text(&code);
+            return;
         }
+        let mut longest_run = 0;
+        let mut run = 0;
+        for c in code.chars() {
+            run = if c == '`' { run + 1 } else { 0 };
+            longest_run = longest_run.max(run);
+        }
+        let fence = "`".repeat(longest_run + 1);
+        let padding = if code.starts_with('`') || code.ends_with('`') {
+            " "
+        } else {
+            ""
+        };
+        self.flush_break();
+        self.out.push_str(&fence);
+        self.out.push_str(padding);
+        self.out.push_str(&code);
+        self.out.push_str(padding);
+        self.out.push_str(&fence);
+    }
+
+    fn write_text(&mut self, text: &str) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.pending_break = true;
+            }
+            if !line.is_empty() {
+                self.flush_break();
+                self.out.push_str(&escape_markdown(line));
+            }
+        }
+    }
+
+    fn flush_break(&mut self) {
+        if self.pending_break && !self.out.is_empty() {
+            self.out.push_str(Self::LINE_BREAK);
+        }
+        self.pending_break = false;
+    }
+}
 
-        tokens.join("")
+/// Write the destination of a markdown link, in angle brackets if it contains whitespace or parentheses
+fn push_markdown_url(out: &mut String, href: &str) {
+    if href.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        out.push('<');
+        out.push_str(href);
+        out.push('>');
+    } else {
+        out.push_str(href);
+    }
+}
+
+/// In Python, offsets of the text are in code points (indices of Python strings), while
+/// the stored offsets of elements are in bytes
+#[cfg(feature = "python")]
+impl RichText {
+    /// Convert an offset in code points given from Python into a byte offset
+    fn to_py_offset(&self, offset: usize) -> PyResult<usize> {
+        self.to_byte_offset(offset, OffsetUnit::CodePoint)
+            .map_err(into_pyerr)
+    }
+
+    fn get_element_in_unit(&self, id: usize, unit: OffsetUnit) -> PyResult<RichTextElement> {
+        let mut element = self.get_element_by_id(id);
+        element.start = self
+            .from_byte_offset(element.start, unit)
+            .map_err(into_pyerr)?;
+        element.end = self
+            .from_byte_offset(element.end, unit)
+            .map_err(into_pyerr)?;
+        Ok(element)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl RichText {
+    #[new]
+    fn py_new() -> Self {
+        RichText::empty()
+    }
+
+    #[getter]
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_str")]
+    fn py_from_str(text: &str) -> RichText {
+        RichText::from_str(text)
     }
 
+    /// Length of the text in code points, i.e., `len(text)`
+    #[pyo3(name = "len")]
+    fn py_len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// Length of the text in bytes of its UTF-8 encoding
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+
+    #[pyo3(name = "iter_element_id")]
+    fn py_iter_element_id(&self) -> RangeIter {
+        self.iter_element_id()
+    }
+
+    fn iter_element_id_preorder(slf: Py<RichText>, py: Python) -> RichTextElementIdPreorderIter {
+        RichTextElementIdPreorderIter::new(slf.clone_ref(py))
+    }
+
+    #[pyo3(name = "get_element_tag_by_id")]
+    fn py_get_element_tag_by_id(&self, id: usize) -> String {
+        self.get_element_tag_by_id(id)
+    }
+
+    /// Get a copy of an element, with its offsets in the unit: "codepoint" (default), "utf16"
+    /// or "byte"
+    #[pyo3(name = "get_element_by_id")]
+    #[args(unit = "\"codepoint\"")]
+    fn py_get_element_by_id(&self, id: usize, unit: &str) -> PyResult<RichTextElement> {
+        let unit = unit.parse::<OffsetUnit>().map_err(PyValueError::new_err)?;
+        self.get_element_in_unit(id, unit)
+    }
+
+    /// Replace an element, its offsets are in the unit: "codepoint" (default), "utf16" or "byte"
+    #[pyo3(name = "set_element_by_id")]
+    #[args(unit = "\"codepoint\"")]
+    fn py_set_element_by_id(
+        &mut self,
+        id: usize,
+        mut element: RichTextElement,
+        unit: &str,
+    ) -> PyResult<()> {
+        let unit = unit.parse::<OffsetUnit>().map_err(PyValueError::new_err)?;
+        element.start = self
+            .to_byte_offset(element.start, unit)
+            .map_err(into_pyerr)?;
+        element.end = self.to_byte_offset(element.end, unit).map_err(into_pyerr)?;
+        self.set_element_by_id(id, element);
+        Ok(())
+    }
+
+    /// Convert an offset of the text from one unit to another ("codepoint", "utf16" or "byte")
+    #[pyo3(name = "convert_offset")]
+    #[args(from_unit = "\"codepoint\"", to_unit = "\"utf16\"")]
+    fn py_convert_offset(&self, offset: usize, from_unit: &str, to_unit: &str) -> PyResult<usize> {
+        let from_unit = from_unit
+            .parse::<OffsetUnit>()
+            .map_err(PyValueError::new_err)?;
+        let to_unit = to_unit
+            .parse::<OffsetUnit>()
+            .map_err(PyValueError::new_err)?;
+        self.convert_offset(offset, from_unit, to_unit)
+            .map_err(into_pyerr)
+    }
+
+    #[pyo3(name = "set_element_attr_by_id")]
+    fn py_set_element_attr_by_id(&mut self, id: usize, attr: &str, value: &str) {
+        self.set_element_attr_by_id(id, attr, value)
+    }
+
+    #[pyo3(name = "get_element_attr_by_id")]
+    fn py_get_element_attr_by_id(&self, id: usize, attr: &str) -> Option<String> {
+        self.get_element_attr_by_id(id, attr)
+    }
+
+    /// Render the text as HTML, with the attributes of the elements if `render_element_attrs`
+    /// is true, or with only the attributes in `allowed_attrs` if it is given
+    #[pyo3(name = "to_html")]
+    #[args(
+        "*",
+        render_outer_element = "true",
+        render_element_attrs = "false",
+        allowed_attrs = "None"
+    )]
+    fn py_to_html(
+        &self,
+        render_outer_element: bool,
+        render_element_attrs: bool,
+        allowed_attrs: Option<Vec<&str>>,
+    ) -> String {
+        match allowed_attrs {
+            Some(allowed_attrs) => {
+                self.to_html_with_attrs(render_outer_element, Some(&allowed_attrs))
+            }
+            None => self.to_html(render_outer_element, render_element_attrs),
+        }
+    }
+
+    #[pyo3(name = "to_markdown")]
+    fn py_to_markdown(&self) -> String {
+        self.to_markdown()
+    }
+
+    #[pyo3(name = "to_text_with_links")]
+    fn py_to_text_with_links(&self) -> String {
+        self.to_text_with_links()
+    }
+
+    /// Convert to a dictionary. Like the other serializations, the offsets of the elements are
+    /// in bytes, which is recorded in the "offset_unit" key.
     pub fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
         let tree = PyDict::new(py);
 
@@ -254,9 +978,12 @@ impl RichText {
         let d = PyDict::new(py);
         d.set_item("text", &self.text)?;
         d.set_item("element", tree)?;
+        d.set_item("offset_unit", OffsetUnit::Byte.as_str())?;
         Ok(d.into_py(py))
     }
 
+    /// Create from a dictionary of [`RichText::to_dict`]. The offsets are in the unit of its
+    /// "offset_unit" key, or in bytes for dictionaries without it.
     #[staticmethod]
     pub fn from_dict(obj: &PyDict) -> PyResult<Self> {
         let text = obj
@@ -285,32 +1012,228 @@ impl RichText {
                 PyErr::new::<pyo3::exceptions::PyKeyError, _>("node2children in element")
             })?
             .extract::<Vec<Vec<usize>>>()?;
+        let unit = match obj.get_item("offset_unit") {
+            None => OffsetUnit::Byte,
+            Some(unit) => unit
+                .extract::<&str>()?
+                .parse::<OffsetUnit>()
+                .map_err(PyValueError::new_err)?,
+        };
 
-        Ok(RichText {
+        let mut text = RichText {
             text,
             element: SimpleTree::from_data(root, nodes, node2children),
-        })
+        };
+        let offsets = text
+            .element
+            .iter()
+            .iter()
+            .map(|el| {
+                Ok((
+                    text.to_byte_offset(el.start, unit)?,
+                    text.to_byte_offset(el.end, unit)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, RSoupError>>()
+            .map_err(into_pyerr)?;
+        for (el, (start, end)) in text.element.iter_mut().iter_mut().zip(offsets) {
+            el.start = start;
+            el.end = end;
+        }
+        Ok(text)
     }
 
-    #[new]
-    pub fn new() -> Self {
-        RichText::empty()
+    /// Ids of the elements matching a CSS selector (e.g., "a[href]"), in preorder
+    #[pyo3(name = "select")]
+    fn py_select(&self, query: &str) -> PyResult<Vec<usize>> {
+        let selector = query
+            .parse::<RichTextSelector>()
+            .map_err(PyValueError::new_err)?;
+        Ok(self.select(&selector))
+    }
+
+    /// Ids of the elements containing the offset, from the outer element to the innermost one
+    #[pyo3(name = "elements_at")]
+    fn py_elements_at(&self, offset: usize) -> PyResult<Vec<usize>> {
+        Ok(self.elements_at(self.to_py_offset(offset)?))
+    }
+
+    /// Part of the text in the range, with the elements overlapping the range clipped to it
+    #[pyo3(name = "slice")]
+    fn py_slice(&self, start: usize, end: usize) -> PyResult<RichText> {
+        self.slice(self.to_py_offset(start)?, self.to_py_offset(end)?)
+            .map_err(into_pyerr)
+    }
+
+    /// Concatenate two rich texts, the outer element of the result is a pseudo-element
+    #[pyo3(name = "concat")]
+    fn py_concat(&self, other: &RichText) -> RichText {
+        self.concat(other)
+    }
+
+    /// Replace the text in the range with a string, keeping the elements around it
+    #[pyo3(name = "replace")]
+    fn py_replace(&self, start: usize, end: usize, value: &str) -> PyResult<RichText> {
+        self.replace(self.to_py_offset(start)?..self.to_py_offset(end)?, value)
+            .map_err(into_pyerr)
+    }
+
+    /// Insert a string at the offset, it belongs to the elements strictly containing the offset
+    #[pyo3(name = "insert")]
+    fn py_insert(&self, offset: usize, value: &str) -> PyResult<RichText> {
+        self.insert(self.to_py_offset(offset)?, value)
+            .map_err(into_pyerr)
+    }
+
+    /// Remove the elements matching a CSS selector (e.g., "sup.reference") or a function
+    /// receiving an element, and shift the offsets of the remaining elements.
+    #[pyo3(name = "remove_elements")]
+    fn py_remove_elements(&self, predicate: &PyAny) -> PyResult<RichText> {
+        if let Ok(selector) = predicate.extract::<&str>() {
+            let selector = selector
+                .parse::<RichTextSelector>()
+                .map_err(PyValueError::new_err)?;
+            return Ok(self.remove_elements(&selector));
+        }
+
+        let mut is_removed = vec![false; self.element.len()];
+        for (id, removed) in is_removed.iter_mut().enumerate() {
+            let element = self.get_element_in_unit(id, OffsetUnit::CodePoint)?;
+            *removed = predicate.call1((element,))?.is_true()?;
+        }
+        Ok(self.remove_elements_where(|path| is_removed[path[path.len() - 1]]))
+    }
+
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> PyResult<String> {
+        self.to_json().map_err(into_pyerr)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_json")]
+    fn py_from_json(dat: &str) -> PyResult<RichText> {
+        RichText::from_json(dat).map_err(into_pyerr)
+    }
+
+    #[pyo3(name = "to_bytes")]
+    fn py_to_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let out = self.to_bytes().map_err(into_pyerr)?;
+        Ok(PyBytes::new(py, &out))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_bytes")]
+    fn py_from_bytes(dat: &PyBytes) -> PyResult<RichText> {
+        RichText::from_bytes(dat.as_bytes()).map_err(into_pyerr)
     }
 
     pub fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
         // Implementing pickling support according to this issue: https://github.com/PyO3/pyo3/issues/100
-        let out = to_allocvec(&self).map_err(into_pyerr)?;
+        let out = self.to_bytes().map_err(into_pyerr)?;
         Ok(PyBytes::new(py, &out))
     }
 
     pub fn __setstate__(&mut self, state: &PyBytes) -> PyResult<()> {
-        *self = from_bytes::<RichText>(state.as_bytes()).map_err(into_pyerr)?;
+        *self = RichText::from_bytes(state.as_bytes()).map_err(into_pyerr)?;
         Ok(())
     }
 }
 
+/// Read-only copy of a rich text, returned by the views of tables and contexts (e.g., the value
+/// of a cell) so that modifying it raises an error instead of silently not updating the view.
+/// Modifications are made on a copy and assigned back, e.g., `cell.value = text`.
+#[cfg(feature = "python")]
+#[pyclass(extends = RichText, module = "rsoup.core")]
+pub struct FrozenRichText;
+
+#[cfg(feature = "python")]
+impl FrozenRichText {
+    pub fn new_py(py: Python, text: RichText) -> PyResult<Py<FrozenRichText>> {
+        Py::new(
+            py,
+            PyClassInitializer::from(text).add_subclass(FrozenRichText),
+        )
+    }
+
+    fn frozen_error() -> PyErr {
+        PyTypeError::new_err(
+            "the text is a read-only copy, modify a copy of it (`copy.copy(text)`) and assign it back",
+        )
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl FrozenRichText {
+    #[args(_args = "*", _kwargs = "**")]
+    fn set_element_by_id(&self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(FrozenRichText::frozen_error())
+    }
+
+    #[args(_args = "*", _kwargs = "**")]
+    fn set_element_attr_by_id(&self, _args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        Err(FrozenRichText::frozen_error())
+    }
+
+    fn __setstate__(&self, _state: &PyBytes) -> PyResult<()> {
+        Err(FrozenRichText::frozen_error())
+    }
+
+    /// Pickled and copied as a mutable rich text
+    fn __reduce__<'py>(
+        slf: PyRef<'py, Self>,
+        py: Python<'py>,
+    ) -> PyResult<(PyObject, (&'py PyBytes,))> {
+        let from_bytes = py.get_type::<RichText>().getattr("from_bytes")?;
+        Ok((from_bytes.into(), (slf.as_ref().__getstate__(py)?,)))
+    }
+}
+
+impl RichTextElement {
+    /// Encode in the versioned binary format, see [`crate::models::format`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RSoupError> {
+        format::encode(DataKind::RichTextElement, self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<RichTextElement, RSoupError> {
+        format::decode(DataKind::RichTextElement, bytes)
+    }
+
+    pub fn to_json(&self) -> Result<String, RSoupError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(dat: &str) -> Result<RichTextElement, RSoupError> {
+        Ok(serde_json::from_str(dat)?)
+    }
+}
+
+#[cfg(feature = "python")]
 #[pymethods]
 impl RichTextElement {
+    #[getter]
+    fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Start of the element, in code points of the text unless the element was requested
+    /// in another unit (see `RichText.get_element_by_id`)
+    #[getter]
+    fn start(&self) -> usize {
+        self.start
+    }
+
+    /// End of the element (exclusive), in the same unit as `start`
+    #[getter]
+    fn end(&self) -> usize {
+        self.end
+    }
+
+    #[getter]
+    fn attrs(&self) -> HashMap<String, String> {
+        self.attrs.clone()
+    }
+
     fn get_attr(&self, name: &str) -> PyResult<&String> {
         self.attrs
             .get(name)
@@ -321,6 +1244,29 @@ impl RichTextElement {
         Ok(self.attrs.contains_key(name))
     }
 
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> PyResult<String> {
+        self.to_json().map_err(into_pyerr)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_json")]
+    fn py_from_json(dat: &str) -> PyResult<RichTextElement> {
+        RichTextElement::from_json(dat).map_err(into_pyerr)
+    }
+
+    #[pyo3(name = "to_bytes")]
+    fn py_to_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let out = self.to_bytes().map_err(into_pyerr)?;
+        Ok(PyBytes::new(py, &out))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_bytes")]
+    fn py_from_bytes(dat: &PyBytes) -> PyResult<RichTextElement> {
+        RichTextElement::from_bytes(dat.as_bytes()).map_err(into_pyerr)
+    }
+
     fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
         let d = PyDict::new(py);
         d.set_item("tag", &self.tag)?;
@@ -369,6 +1315,7 @@ impl fmt::Debug for RichText {
     }
 }
 
+#[cfg(feature = "python")]
 #[pyclass(module = "rsoup.core")]
 pub struct RichTextElementIdPreorderIter {
     text: Py<RichText>,
@@ -376,6 +1323,7 @@ pub struct RichTextElementIdPreorderIter {
     inited: bool,
 }
 
+#[cfg(feature = "python")]
 impl RichTextElementIdPreorderIter {
     pub fn new(text: Py<RichText>) -> Self {
         RichTextElementIdPreorderIter {
@@ -386,6 +1334,7 @@ impl RichTextElementIdPreorderIter {
     }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl RichTextElementIdPreorderIter {
     fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
@@ -395,7 +1344,7 @@ impl RichTextElementIdPreorderIter {
     fn __next__(&mut self, py: Python) -> Option<usize> {
         let text = self.text.borrow(py);
         loop {
-            if self.stack.len() == 0 {
+            if self.stack.is_empty() {
                 if self.inited {
                     return None;
                 }
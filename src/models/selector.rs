@@ -0,0 +1,281 @@
+//! A small subset of CSS selectors to match the elements of a [`RichText`].
+//!
+//! Supported: type (`a`) and universal (`*`) selectors, classes (`.reference`), ids (`#cite`),
+//! attributes (`[href]`, `[href="..."]`, `[class~=...]`, `[href^=...]`, `[href$=...]`,
+//! `[href*=...]`), the descendant (`sup a`) and child (`sup > a`) combinators, and lists of
+//! selectors (`sup, small`).
+
+use std::str::FromStr;
+
+use crate::models::rich_text::{RichText, RichTextElement};
+
+/// A list of selectors, an element matches if it matches any of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RichTextSelector {
+    selectors: Vec<ComplexSelector>,
+}
+
+/// Compound selectors joined by combinators, e.g., `sup.reference > a`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ComplexSelector {
+    // the combinator of a compound joins it with the previous compound
+    compounds: Vec<(Combinator, CompoundSelector)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CompoundSelector {
+    tag: Option<String>,
+    classes: Vec<String>,
+    id: Option<String>,
+    attrs: Vec<AttrSelector>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AttrSelector {
+    name: String,
+    op: AttrOp,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrOp {
+    Exists,
+    Equals,
+    Includes,
+    Prefix,
+    Suffix,
+    Substring,
+}
+
+impl RichTextSelector {
+    /// Whether the element at the end of `path` matches the selector. `path` is the ids of
+    /// the element and of its ancestors, starting from the root of the rich text.
+    pub fn matches(&self, text: &RichText, path: &[usize]) -> bool {
+        self.selectors
+            .iter()
+            .any(|selector| selector.matches(text, path, selector.compounds.len(), path.len()))
+    }
+}
+
+impl ComplexSelector {
+    /// Whether the first `n_compounds` compounds match the element `path[n_path - 1]`
+    fn matches(&self, text: &RichText, path: &[usize], n_compounds: usize, n_path: usize) -> bool {
+        let (combinator, compound) = &self.compounds[n_compounds - 1];
+        if n_path == 0 || !compound.matches(text.element.get_node(path[n_path - 1])) {
+            return false;
+        }
+        if n_compounds == 1 {
+            return true;
+        }
+        match combinator {
+            Combinator::Child => self.matches(text, path, n_compounds - 1, n_path - 1),
+            Combinator::Descendant => (1..n_path)
+                .rev()
+                .any(|n| self.matches(text, path, n_compounds - 1, n)),
+        }
+    }
+}
+
+impl CompoundSelector {
+    fn matches(&self, element: &RichTextElement) -> bool {
+        if let Some(tag) = &self.tag {
+            if !element.tag.eq_ignore_ascii_case(tag) {
+                return false;
+            }
+        }
+        if let Some(id) = &self.id {
+            if element.attrs.get("id") != Some(id) {
+                return false;
+            }
+        }
+        if !self.classes.is_empty() {
+            let classes = element.attrs.get("class").map_or("", String::as_str);
+            if !self
+                .classes
+                .iter()
+                .all(|cls| classes.split_whitespace().any(|c| c == cls))
+            {
+                return false;
+            }
+        }
+        self.attrs.iter().all(|attr| {
+            let value = match element.attrs.get(&attr.name) {
+                None => return false,
+                Some(value) => value,
+            };
+            match attr.op {
+                AttrOp::Exists => true,
+                AttrOp::Equals => *value == attr.value,
+                AttrOp::Includes => value.split_whitespace().any(|v| v == attr.value),
+                AttrOp::Prefix => !attr.value.is_empty() && value.starts_with(&attr.value),
+                AttrOp::Suffix => !attr.value.is_empty() && value.ends_with(&attr.value),
+                AttrOp::Substring => !attr.value.is_empty() && value.contains(&attr.value),
+            }
+        })
+    }
+}
+
+impl FromStr for RichTextSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            query: s,
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let mut selectors = vec![parser.parse_complex()?];
+        while parser.peek() == Some(',') {
+            parser.pos += 1;
+            selectors.push(parser.parse_complex()?);
+        }
+        Ok(RichTextSelector { selectors })
+    }
+}
+
+struct Parser<'s> {
+    query: &'s str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn error(&self, reason: &str) -> String {
+        format!("Invalid selector '{}': {}", self.query, reason)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn parse_complex(&mut self) -> Result<ComplexSelector, String> {
+        self.skip_whitespace();
+        let mut compounds = vec![(Combinator::Descendant, self.parse_compound()?)];
+        loop {
+            let has_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                None | Some(',') => break,
+                Some('>') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    Combinator::Child
+                }
+                Some(_) if has_whitespace => Combinator::Descendant,
+                Some(c) => return Err(self.error(&format!("unexpected character '{}'", c))),
+            };
+            compounds.push((combinator, self.parse_compound()?));
+        }
+        Ok(ComplexSelector { compounds })
+    }
+
+    fn parse_compound(&mut self) -> Result<CompoundSelector, String> {
+        let mut compound = CompoundSelector::default();
+        let start = self.pos;
+        match self.peek() {
+            Some('*') => self.pos += 1,
+            Some(c) if is_ident_char(c) => compound.tag = Some(self.parse_ident()?),
+            _ => {}
+        }
+        loop {
+            match self.peek() {
+                Some('.') => {
+                    self.pos += 1;
+                    compound.classes.push(self.parse_ident()?);
+                }
+                Some('#') => {
+                    self.pos += 1;
+                    compound.id = Some(self.parse_ident()?);
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    compound.attrs.push(self.parse_attr()?);
+                }
+                _ => break,
+            }
+        }
+        if self.pos == start {
+            return Err(self.error("expect a tag, a class, an id or an attribute"));
+        }
+        Ok(compound)
+    }
+
+    fn parse_attr(&mut self) -> Result<AttrSelector, String> {
+        self.skip_whitespace();
+        let name = self.parse_ident()?;
+        self.skip_whitespace();
+        let op = match self.peek() {
+            Some(']') => {
+                self.pos += 1;
+                return Ok(AttrSelector {
+                    name,
+                    op: AttrOp::Exists,
+                    value: String::new(),
+                });
+            }
+            Some('=') => AttrOp::Equals,
+            Some('~') => AttrOp::Includes,
+            Some('^') => AttrOp::Prefix,
+            Some('$') => AttrOp::Suffix,
+            Some('*') => AttrOp::Substring,
+            _ => return Err(self.error("expect an attribute operator or ']'")),
+        };
+        self.pos += 1;
+        if op != AttrOp::Equals {
+            if self.peek() != Some('=') {
+                return Err(self.error("expect '=' in the attribute operator"));
+            }
+            self.pos += 1;
+        }
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != quote) {
+                    self.pos += 1;
+                }
+                if self.peek().is_none() {
+                    return Err(self.error("unterminated string"));
+                }
+                self.pos += 1;
+                self.chars[start..self.pos - 1].iter().collect()
+            }
+            _ => self.parse_ident()?,
+        };
+        self.skip_whitespace();
+        if self.peek() != Some(']') {
+            return Err(self.error("expect ']'"));
+        }
+        self.pos += 1;
+        Ok(AttrSelector { name, op, value })
+    }
+
+    fn parse_ident(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_ident_char) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error("expect an identifier"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == ':'
+}
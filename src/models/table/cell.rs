@@ -1,35 +1,64 @@
 use hashbrown::HashMap;
-use pyo3::{prelude::*, types::PyDict};
 use serde::{Deserialize, Serialize};
-use std::fmt;
 
-use crate::models::rich_text::RichText;
+#[cfg(feature = "python")]
+use crate::error::into_pyerr;
+#[cfg(feature = "python")]
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
 
-#[pyclass(module = "rsoup.core")]
-#[derive(Clone, Deserialize, Serialize)]
+use super::{legacy::CellV2, Table};
+#[cfg(feature = "python")]
+use super::{row::removed_error, PyRow, PyTable, TypedValue, ValueParser};
+use crate::error::RSoupError;
+#[cfg(feature = "python")]
+use crate::models::rich_text::FrozenRichText;
+use crate::models::{
+    format::{self, DataKind},
+    rich_text::RichText,
+};
+
+/// Where a cell of a spanned table comes from: position of the merged cell
+/// in the spanned table and its rowspan/colspan before spanning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CellOrigin {
+    pub row: usize,
+    pub col: usize,
+    pub rowspan: u16,
+    pub colspan: u16,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Cell {
-    #[pyo3(get, set)]
     pub is_header: bool,
-    #[pyo3(get, set)]
     pub rowspan: u16,
-    #[pyo3(get, set)]
     pub colspan: u16,
-    #[pyo3(get)]
     pub attrs: HashMap<String, String>,
     // include the outer tags of the cell
-    #[pyo3(get, set)]
-    pub value: Py<RichText>,
+    pub value: RichText,
+    // tables inside the cell, only extracted when nested tables are enabled. they are kept
+    // in the top-left cell of a merged cell only, not in its span copies
+    #[serde(default)]
+    pub nested_tables: Vec<Table>,
+    // only available after the table is spanned
+    #[serde(default)]
+    pub origin: Option<CellOrigin>,
+    // whether the cell is a copy of a merged cell made when spanning the table (i.e., not
+    // at the top-left position of the merged cell)
+    #[serde(default)]
+    pub is_span_copy: bool,
 }
 
-#[pymethods]
 impl Cell {
-    #[new]
     pub fn new(
         is_header: bool,
         rowspan: u16,
         colspan: u16,
         attrs: HashMap<String, String>,
-        value: Py<RichText>,
+        value: RichText,
     ) -> Self {
         Cell {
             is_header,
@@ -37,35 +66,285 @@ impl Cell {
             colspan,
             attrs,
             value,
+            nested_tables: Vec::new(),
+            origin: None,
+            is_span_copy: false,
+        }
+    }
+
+    /// Copy of the cell for the other positions of a merged cell when spanning the table,
+    /// without the nested tables that stay in the top-left cell
+    pub(super) fn span_copy(&self) -> Cell {
+        Cell {
+            is_header: self.is_header,
+            rowspan: self.rowspan,
+            colspan: self.colspan,
+            attrs: self.attrs.clone(),
+            value: self.value.clone(),
+            nested_tables: Vec::new(),
+            origin: self.origin,
+            is_span_copy: true,
+        }
+    }
+
+    /// Encode in the versioned binary format, see [`crate::models::format`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RSoupError> {
+        format::encode(DataKind::Cell, self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Cell, RSoupError> {
+        let (version, payload) = format::decode_header(DataKind::Cell, bytes)?;
+        match version {
+            1 => Ok(format::decode_postcard::<CellV2>(DataKind::Cell, version, payload)?.into()),
+            _ => format::decode_postcard(DataKind::Cell, version, payload),
         }
     }
 
-    fn __str__(&self, py: Python) -> String {
-        self.value.borrow(py).to_html(true, false)
+    pub fn to_json(&self) -> Result<String, RSoupError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(dat: &str) -> Result<Cell, RSoupError> {
+        Ok(serde_json::from_str(dat)?)
     }
+}
+
+/// Python wrapper of [`Cell`]. A cell obtained from a row is a view of the cell in the row:
+/// reading or modifying it reads or modifies the row. Its value, attributes and nested tables
+/// are returned as copies, assign `value` to modify the text of the cell.
+#[cfg(feature = "python")]
+#[pyclass(module = "rsoup.core", name = "Cell")]
+pub struct PyCell {
+    data: CellData,
+}
 
-    pub(super) fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+#[cfg(feature = "python")]
+enum CellData {
+    Owned(Cell),
+    InRow { row: Py<PyRow>, index: usize },
+}
+
+#[cfg(feature = "python")]
+impl From<Cell> for PyCell {
+    fn from(cell: Cell) -> Self {
+        PyCell {
+            data: CellData::Owned(cell),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl PyCell {
+    /// View of the cell at `index` of the row
+    pub fn in_row(row: Py<PyRow>, index: usize) -> Self {
+        PyCell {
+            data: CellData::InRow { row, index },
+        }
+    }
+
+    pub fn with_cell<R>(&self, py: Python, f: impl FnOnce(&Cell) -> R) -> PyResult<R> {
+        match &self.data {
+            CellData::Owned(cell) => Ok(f(cell)),
+            CellData::InRow { row, index } => row
+                .try_borrow(py)?
+                .with_row(py, |row| row.cells.get(*index).map(f))?
+                .ok_or_else(|| removed_error("cell", *index)),
+        }
+    }
+
+    pub fn with_cell_mut<R>(&mut self, py: Python, f: impl FnOnce(&mut Cell) -> R) -> PyResult<R> {
+        match &mut self.data {
+            CellData::Owned(cell) => Ok(f(cell)),
+            CellData::InRow { row, index } => row
+                .try_borrow_mut(py)?
+                .with_row_mut(py, |row| row.cells.get_mut(*index).map(f))?
+                .ok_or_else(|| removed_error("cell", *index)),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl Cell {
+    pub fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
         let o = PyDict::new(py);
 
-        o.set_item("is_header", &self.is_header)?;
+        o.set_item("is_header", self.is_header)?;
         o.set_item("rowspan", self.rowspan)?;
         o.set_item("colspan", self.colspan)?;
         o.set_item("attrs", &self.attrs)?;
-        o.set_item("value", self.value.borrow(py).to_dict(py)?)?;
+        o.set_item("value", self.value.to_dict(py)?)?;
+        o.set_item(
+            "nested_tables",
+            &self
+                .nested_tables
+                .iter()
+                .map(|t| t.to_dict(py))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?;
+        match self.origin {
+            None => o.set_item("origin", py.None())?,
+            Some(origin) => {
+                let d = PyDict::new(py);
+                d.set_item("row", origin.row)?;
+                d.set_item("col", origin.col)?;
+                d.set_item("rowspan", origin.rowspan)?;
+                d.set_item("colspan", origin.colspan)?;
+                o.set_item("origin", d)?;
+            }
+        }
+        o.set_item("is_span_copy", self.is_span_copy)?;
         Ok(o.into_py(py))
     }
 }
 
-impl fmt::Debug for Cell {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        Python::with_gil(|py| {
-            f.debug_struct("Cell")
-                .field("is_header", &self.is_header)
-                .field("rowspan", &self.rowspan)
-                .field("colspan", &self.colspan)
-                .field("attrs", &self.attrs)
-                .field("value", &self.value.borrow(py))
-                .finish()
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyCell {
+    #[new]
+    #[args(nested_tables = "Vec::new()")]
+    pub fn new(
+        is_header: bool,
+        rowspan: u16,
+        colspan: u16,
+        attrs: HashMap<String, String>,
+        value: PyRef<RichText>,
+        nested_tables: Vec<PyRef<PyTable>>,
+    ) -> Self {
+        let mut cell = Cell::new(is_header, rowspan, colspan, attrs, value.clone());
+        cell.nested_tables = nested_tables.iter().map(|t| t.table.clone()).collect();
+        PyCell::from(cell)
+    }
+
+    #[getter]
+    fn is_header(&self, py: Python) -> PyResult<bool> {
+        self.with_cell(py, |cell| cell.is_header)
+    }
+
+    #[setter]
+    fn set_is_header(&mut self, py: Python, is_header: bool) -> PyResult<()> {
+        self.with_cell_mut(py, |cell| cell.is_header = is_header)
+    }
+
+    #[getter]
+    fn rowspan(&self, py: Python) -> PyResult<u16> {
+        self.with_cell(py, |cell| cell.rowspan)
+    }
+
+    #[setter]
+    fn set_rowspan(&mut self, py: Python, rowspan: u16) -> PyResult<()> {
+        self.with_cell_mut(py, |cell| cell.rowspan = rowspan)
+    }
+
+    #[getter]
+    fn colspan(&self, py: Python) -> PyResult<u16> {
+        self.with_cell(py, |cell| cell.colspan)
+    }
+
+    #[setter]
+    fn set_colspan(&mut self, py: Python, colspan: u16) -> PyResult<()> {
+        self.with_cell_mut(py, |cell| cell.colspan = colspan)
+    }
+
+    #[getter]
+    fn attrs(&self, py: Python) -> PyResult<HashMap<String, String>> {
+        self.with_cell(py, |cell| cell.attrs.clone())
+    }
+
+    /// Read-only copy of the text of the cell, including the outer tags of the cell
+    #[getter]
+    fn value(&self, py: Python) -> PyResult<Py<FrozenRichText>> {
+        let value = self.with_cell(py, |cell| cell.value.clone())?;
+        FrozenRichText::new_py(py, value)
+    }
+
+    #[setter]
+    fn set_value(&mut self, py: Python, value: PyRef<RichText>) -> PyResult<()> {
+        self.with_cell_mut(py, |cell| cell.value = value.clone())
+    }
+
+    #[getter]
+    fn nested_tables(&self, py: Python) -> PyResult<Vec<PyTable>> {
+        self.with_cell(py, |cell| {
+            cell.nested_tables
+                .iter()
+                .cloned()
+                .map(PyTable::from)
+                .collect()
         })
     }
+
+    #[getter]
+    fn is_span_copy(&self, py: Python) -> PyResult<bool> {
+        self.with_cell(py, |cell| cell.is_span_copy)
+    }
+
+    #[setter]
+    fn set_is_span_copy(&mut self, py: Python, is_span_copy: bool) -> PyResult<()> {
+        self.with_cell_mut(py, |cell| cell.is_span_copy = is_span_copy)
+    }
+
+    /// Row of the merged cell that this cell comes from, only available after spanning
+    #[getter]
+    fn origin_row(&self, py: Python) -> PyResult<Option<usize>> {
+        self.with_cell(py, |cell| cell.origin.map(|o| o.row))
+    }
+
+    /// Column of the merged cell that this cell comes from, only available after spanning
+    #[getter]
+    fn origin_col(&self, py: Python) -> PyResult<Option<usize>> {
+        self.with_cell(py, |cell| cell.origin.map(|o| o.col))
+    }
+
+    /// Rowspan of the merged cell before spanning
+    #[getter]
+    fn original_rowspan(&self, py: Python) -> PyResult<Option<u16>> {
+        self.with_cell(py, |cell| cell.origin.map(|o| o.rowspan))
+    }
+
+    /// Colspan of the merged cell before spanning
+    #[getter]
+    fn original_colspan(&self, py: Python) -> PyResult<Option<u16>> {
+        self.with_cell(py, |cell| cell.origin.map(|o| o.colspan))
+    }
+
+    fn __str__(&self, py: Python) -> PyResult<String> {
+        self.with_cell(py, |cell| cell.value.to_html(true, false))
+    }
+
+    /// Parse the text of the cell into a typed value, with the decimal and thousands
+    /// separators of the locale, e.g., "en", "de" or "fr"
+    #[args(locale = "\"en\"")]
+    pub fn parse_value(&self, py: Python, locale: &str) -> PyResult<TypedValue> {
+        let parser = ValueParser::from_locale(locale).map_err(PyValueError::new_err)?;
+        self.with_cell(py, |cell| cell.parse_value(&parser))
+    }
+
+    pub fn to_json(&self, py: Python) -> PyResult<String> {
+        self.with_cell(py, |cell| cell.to_json())?
+            .map_err(into_pyerr)
+    }
+
+    #[staticmethod]
+    pub fn from_json(dat: &str) -> PyResult<PyCell> {
+        Ok(PyCell::from(Cell::from_json(dat).map_err(into_pyerr)?))
+    }
+
+    pub fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let out = self
+            .with_cell(py, |cell| cell.to_bytes())?
+            .map_err(into_pyerr)?;
+        Ok(PyBytes::new(py, &out))
+    }
+
+    #[staticmethod]
+    pub fn from_bytes(dat: &PyBytes) -> PyResult<PyCell> {
+        Ok(PyCell::from(
+            Cell::from_bytes(dat.as_bytes()).map_err(into_pyerr)?,
+        ))
+    }
+
+    #[pyo3(name = "to_dict")]
+    fn py_to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+        self.with_cell(py, |cell| cell.to_dict(py))?
+    }
 }
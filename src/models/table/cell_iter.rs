@@ -1,10 +1,10 @@
 use pyo3::prelude::*;
 
-use super::{Cell, Row, Table};
+use super::{PyCell, PyRow, PyTable};
 
 #[pyclass(module = "rsoup.core", unsendable)]
 pub struct CellRIter {
-    pub row: Py<Row>,
+    pub row: Py<PyRow>,
     pub cell_index: usize,
 }
 
@@ -14,21 +14,21 @@ impl CellRIter {
         slf
     }
 
-    fn __next__(&mut self, py: Python) -> Option<Py<Cell>> {
-        let row = self.row.borrow(py);
-        if self.cell_index >= row.cells.len() {
-            None
+    fn __next__(&mut self, py: Python) -> PyResult<Option<PyCell>> {
+        let n_cells = self.row.borrow(py).with_row(py, |row| row.cells.len())?;
+        if self.cell_index >= n_cells {
+            Ok(None)
         } else {
-            let cell = row.cells[self.cell_index].clone_ref(py);
+            let cell = PyCell::in_row(self.row.clone_ref(py), self.cell_index);
             self.cell_index += 1;
-            Some(cell)
+            Ok(Some(cell))
         }
     }
 }
 
 #[pyclass(module = "rsoup.core", unsendable)]
 pub struct CellTIter {
-    pub table: Py<Table>,
+    pub table: Py<PyTable>,
     pub row_index: usize,
     pub cell_index: usize,
 }
@@ -39,40 +39,17 @@ impl CellTIter {
         slf
     }
 
-    fn __next__(&mut self, py: Python) -> Option<Py<Cell>> {
-        let table = self.table.borrow(py);
-
-        if self.row_index >= table.rows.len() {
-            return None;
-        }
-
-        let mut row = table.rows[self.row_index].borrow(py);
-
-        if self.cell_index < row.cells.len() {
-            self.cell_index += 1;
-            return Some(row.cells[self.cell_index - 1].clone_ref(py));
-        }
-
-        // exhausted row, move to next row
-        loop {
-            self.row_index += 1;
-            if self.row_index >= table.rows.len() {
-                return None;
-            }
-            row = table.rows[self.row_index].borrow(py);
-            if row.cells.len() > 0 {
-                break;
-            }
-        }
-
-        self.cell_index = 1;
-        Some(row.cells[0].clone_ref(py))
+    fn __next__(&mut self, py: Python) -> PyResult<Option<PyCell>> {
+        Ok(
+            next_cell(py, &self.table, &mut self.row_index, &mut self.cell_index)?
+                .map(|(_, _, cell)| cell),
+        )
     }
 }
 
 #[pyclass(module = "rsoup.core", unsendable)]
 pub struct CellTEnumerator {
-    pub table: Py<Table>,
+    pub table: Py<PyTable>,
     pub row_index: usize,
     pub cell_index: usize,
 }
@@ -83,38 +60,30 @@ impl CellTEnumerator {
         slf
     }
 
-    fn __next__(&mut self, py: Python) -> Option<(usize, usize, Py<Cell>)> {
-        let table = self.table.borrow(py);
-
-        if self.row_index >= table.rows.len() {
-            return None;
-        }
-
-        let mut row = table.rows[self.row_index].borrow(py);
-
-        if self.cell_index < row.cells.len() {
-            let return_val = (
-                self.row_index,
-                self.cell_index,
-                row.cells[self.cell_index].clone_ref(py),
-            );
-            self.cell_index += 1;
-            return Some(return_val);
-        }
-
-        // exhausted row, move to next row
-        loop {
-            self.row_index += 1;
-            if self.row_index >= table.rows.len() {
-                return None;
-            }
-            row = table.rows[self.row_index].borrow(py);
-            if row.cells.len() > 0 {
-                break;
-            }
-        }
+    fn __next__(&mut self, py: Python) -> PyResult<Option<(usize, usize, PyCell)>> {
+        next_cell(py, &self.table, &mut self.row_index, &mut self.cell_index)
+    }
+}
 
-        self.cell_index = 1;
-        Some((self.row_index, 0, row.cells[0].clone_ref(py)))
+/// Find the next cell of the table from the position (`row_index`, `cell_index`), skipping
+/// empty rows, and move the position after it
+fn next_cell(
+    py: Python,
+    table: &Py<PyTable>,
+    row_index: &mut usize,
+    cell_index: &mut usize,
+) -> PyResult<Option<(usize, usize, PyCell)>> {
+    let rows = &table.borrow(py).table.rows;
+    while *row_index < rows.len() && *cell_index >= rows[*row_index].cells.len() {
+        *row_index += 1;
+        *cell_index = 0;
     }
+    if *row_index >= rows.len() {
+        return Ok(None);
+    }
+
+    let row = Py::new(py, PyRow::in_table(table.clone_ref(py), *row_index))?;
+    let item = (*row_index, *cell_index, PyCell::in_row(row, *cell_index));
+    *cell_index += 1;
+    Ok(Some(item))
 }
@@ -0,0 +1,62 @@
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "python")]
+use pyo3::{prelude::*, types::PyDict};
+
+/// Column descriptor of a table, authored by `<col>` or `<colgroup>` elements.
+///
+/// Before spanning, a column descriptor may cover several consecutive columns (`span` > 1).
+/// After spanning, there is one descriptor per logical column.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
+pub struct Column {
+    pub span: u16,
+    pub attrs: HashMap<String, String>,
+}
+
+impl Column {
+    pub fn new(span: u16, attrs: HashMap<String, String>) -> Self {
+        Column { span, attrs }
+    }
+}
+
+/// Expand column descriptors so that each of them covers exactly one column
+pub fn expand_columns(columns: &[Column]) -> Vec<Column> {
+    columns
+        .iter()
+        .flat_map(|col| std::iter::repeat_n(Column::new(1, col.attrs.clone()), col.span as usize))
+        .collect()
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Column {
+    #[new]
+    #[args(span = "1", attrs = "HashMap::new()")]
+    fn py_new(span: u16, attrs: HashMap<String, String>) -> Self {
+        Column::new(span, attrs)
+    }
+
+    #[getter]
+    fn span(&self) -> u16 {
+        self.span
+    }
+
+    #[setter]
+    fn set_span(&mut self, span: u16) {
+        self.span = span;
+    }
+
+    #[getter]
+    fn attrs(&self) -> HashMap<String, String> {
+        self.attrs.clone()
+    }
+
+    pub(super) fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+        let o = PyDict::new(py);
+        o.set_item("span", self.span)?;
+        o.set_item("attrs", &self.attrs)?;
+        Ok(o.into_py(py))
+    }
+}
@@ -0,0 +1,370 @@
+use std::str::FromStr;
+
+use hashbrown::HashMap;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{Cell, Row, Table, COLUMN_NAME_SEPARATOR};
+use crate::misc::{escape_html, escape_markdown};
+
+/// How the value of a cell is rendered when a table is exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CellFormat {
+    /// the text of the cell
+    #[default]
+    Text,
+    /// the inner HTML of the cell
+    Html,
+    /// the text of the cell as markdown, see [`crate::models::rich_text::RichText::to_markdown`]
+    Markdown,
+}
+
+impl CellFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CellFormat::Text => "text",
+            CellFormat::Html => "html",
+            CellFormat::Markdown => "markdown",
+        }
+    }
+
+    pub fn render(&self, cell: &Cell) -> String {
+        match self {
+            CellFormat::Text => cell.value.text.clone(),
+            CellFormat::Html => cell.value.to_html(false, true),
+            CellFormat::Markdown => cell.value.to_markdown(),
+        }
+    }
+
+    /// Render a plain text, e.g., a name of a column, in the same way as the text of a cell
+    pub fn render_text(&self, text: &str) -> String {
+        match self {
+            CellFormat::Text => text.to_owned(),
+            CellFormat::Html => escape_html(text),
+            CellFormat::Markdown => escape_markdown(text),
+        }
+    }
+}
+
+impl FromStr for CellFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(CellFormat::Text),
+            "html" => Ok(CellFormat::Html),
+            "markdown" => Ok(CellFormat::Markdown),
+            _ => Err(format!("Invalid cell format: {}", s)),
+        }
+    }
+}
+
+/// Write the table as delimiter-separated values following RFC 4180: records end with CRLF,
+/// and fields containing the delimiter, a double quote, CR or LF are enclosed in double quotes
+/// with their double quotes doubled.
+///
+/// If `header` is false, the leading rows whose cells are all headers are not written.
+pub fn to_delimited(table: &Table, delimiter: char, format: CellFormat, header: bool) -> String {
+    let n_skipped_rows = if header {
+        0
+    } else {
+        count_header_cell_rows(table)
+    };
+
+    let mut out = String::new();
+    for row in &table.rows[n_skipped_rows..] {
+        for (ci, cell) in row.cells.iter().enumerate() {
+            if ci > 0 {
+                out.push(delimiter);
+            }
+            write_field(&mut out, &format.render(cell), delimiter);
+        }
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Write the table as a GitHub-flavored markdown table. `|` in cells are escaped and line
+/// breaks are written as `<br>`. Cells longer than `max_col_width` (in display width) are
+/// truncated with an ellipsis.
+///
+/// The header rows (see [`Table::n_header_rows`]) are flattened into the header of the
+/// markdown table (see [`Table::flatten_headers`]), stacked header cells are joined with
+/// [`COLUMN_NAME_SEPARATOR`]. If there is no header row, the header of the markdown table is
+/// empty.
+pub fn to_markdown(table: &Table, format: CellFormat, max_col_width: Option<usize>) -> String {
+    let (header, rows) = render_text_rows(table, format, max_col_width, |text| {
+        replace_line_breaks(&text.replace('|', "\\|"), format, "<br>")
+    });
+    let n_cols = header.len();
+
+    let mut out = String::new();
+    write_markdown_row(&mut out, &header);
+    write_markdown_row(&mut out, &vec!["---".to_owned(); n_cols]);
+    for row in &rows {
+        write_markdown_row(&mut out, row);
+    }
+    out
+}
+
+/// Write the table as an aligned plain-text grid, in which the header rows are separated
+/// from the body rows by a `=` line. Line breaks in cells are written as spaces and cells
+/// longer than `max_col_width` (in display width) are truncated with an ellipsis.
+///
+/// Header rows are found in the same way as in [`to_markdown`].
+pub fn to_text_grid(table: &Table, format: CellFormat, max_col_width: Option<usize>) -> String {
+    let (header, rows) = render_text_rows(table, format, max_col_width, |text| {
+        replace_line_breaks(text, format, " ")
+    });
+    let mut widths = vec![0; header.len()];
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        for (width, text) in widths.iter_mut().zip(row) {
+            *width = (*width).max(text.width());
+        }
+    }
+
+    let mut out = String::new();
+    write_grid_line(&mut out, &widths, '-');
+    if header.iter().any(|text| !text.is_empty()) {
+        write_grid_row(&mut out, &widths, &header);
+        write_grid_line(&mut out, &widths, '=');
+    }
+    for row in &rows {
+        write_grid_row(&mut out, &widths, row);
+    }
+    if !rows.is_empty() {
+        write_grid_line(&mut out, &widths, '-');
+    }
+    out
+}
+
+/// Render the flattened header and the body rows of the table to single-line texts, padding
+/// rows to the number of columns of the table
+fn render_text_rows(
+    table: &Table,
+    format: CellFormat,
+    max_col_width: Option<usize>,
+    escape: impl Fn(&str) -> String,
+) -> (Vec<String>, Vec<Vec<String>>) {
+    let flattened_table = table.flatten_headers(None);
+    let n_cols = flattened_table.column_paths.len();
+    let shorten = |text: String| match max_col_width {
+        Some(max_width) => truncate(&text, max_width),
+        None => text,
+    };
+    let render = |cell: Option<&Cell>| {
+        let text = cell.map(|c| format.render(c)).unwrap_or_default();
+        shorten(escape(text.trim()))
+    };
+
+    let header = flattened_table
+        .column_names(COLUMN_NAME_SEPARATOR)
+        .iter()
+        .map(|name| shorten(escape(&format.render_text(name))))
+        .collect();
+    let rows = flattened_table
+        .rows
+        .iter()
+        .map(|row| (0..n_cols).map(|ci| render(row.cells.get(ci))).collect())
+        .collect();
+    (header, rows)
+}
+
+/// Replace the line breaks of a rendered cell, including the hard line breaks (`\` followed by
+/// a newline) of the markdown format
+fn replace_line_breaks(text: &str, format: CellFormat, replacement: &str) -> String {
+    let text = match format {
+        CellFormat::Markdown => text.replace("\\\n", "\n"),
+        _ => text.to_owned(),
+    };
+    text.replace("\r\n", replacement)
+        .replace(['\r', '\n'], replacement)
+}
+
+/// Shorten a text to a display width, ending it with an ellipsis if it is truncated
+fn truncate(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_owned();
+    }
+    let mut out = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width + 1 > max_width {
+            break;
+        }
+        out.push(c);
+        width += char_width;
+    }
+    if max_width > 0 {
+        out.push('…');
+    }
+    out
+}
+
+fn write_markdown_row(out: &mut String, row: &[String]) {
+    out.push('|');
+    for text in row {
+        out.push(' ');
+        out.push_str(text);
+        out.push_str(" |");
+    }
+    out.push('\n');
+}
+
+fn write_grid_line(out: &mut String, widths: &[usize], c: char) {
+    out.push('+');
+    for width in widths {
+        out.extend(std::iter::repeat_n(c, width + 2));
+        out.push('+');
+    }
+    out.push('\n');
+}
+
+fn write_grid_row(out: &mut String, widths: &[usize], row: &[String]) {
+    out.push('|');
+    for (width, text) in widths.iter().zip(row) {
+        out.push(' ');
+        out.push_str(text);
+        out.extend(std::iter::repeat_n(' ', width - text.width() + 1));
+        out.push('|');
+    }
+    out.push('\n');
+}
+
+/// Number of leading rows of the table whose cells are all headers
+fn count_header_cell_rows(table: &Table) -> usize {
+    table
+        .rows
+        .iter()
+        .take_while(|row| !row.cells.is_empty() && row.cells.iter().all(|c| c.is_header))
+        .count()
+}
+
+/// Write the table as HTML, with its caption, columns, attributes, and rows grouped into
+/// `thead`, `tbody` and `tfoot` elements by their row group.
+///
+/// If `grid` is false, merged cells are written once with `rowspan` and `colspan`: cells of a
+/// spanned table are merged back with their copies, and cells of a table that is not spanned
+/// keep their spans. Otherwise, every cell of the table is written without spans.
+pub fn to_html(table: &Table, grid: bool) -> String {
+    let mut out = String::new();
+    out.push_str("<table");
+    write_attrs(&mut out, &table.attrs, &[]);
+    out.push('>');
+
+    if !table.caption.is_empty() {
+        out.push_str("<caption>");
+        out.push_str(&escape_html(&table.caption));
+        out.push_str("</caption>");
+    }
+
+    if !table.columns.is_empty() {
+        out.push_str("<colgroup>");
+        for col in &table.columns {
+            out.push_str("<col");
+            if col.span != 1 {
+                out.push_str(&format!(" span=\"{}\"", col.span));
+            }
+            write_attrs(&mut out, &col.attrs, &["span"]);
+            out.push('>');
+        }
+        out.push_str("</colgroup>");
+    }
+
+    let mut ri = 0;
+    while ri < table.rows.len() {
+        let first_row = &table.rows[ri];
+        let section = first_row.section;
+        out.push('<');
+        out.push_str(section.as_str());
+        out.push('>');
+        while ri < table.rows.len() && table.rows[ri].is_same_group(first_row) {
+            write_row(&mut out, table, ri, grid);
+            ri += 1;
+        }
+        out.push_str("</");
+        out.push_str(section.as_str());
+        out.push('>');
+    }
+
+    out.push_str("</table>");
+    out
+}
+
+fn write_row(out: &mut String, table: &Table, ri: usize, grid: bool) {
+    let row = &table.rows[ri];
+    out.push_str("<tr");
+    write_attrs(out, &row.attrs, &[]);
+    out.push('>');
+
+    for (ci, cell) in row.cells.iter().enumerate() {
+        let (rowspan, colspan) = if grid {
+            (1, 1)
+        } else if cell.is_span_copy {
+            continue;
+        } else if cell.origin.is_some() {
+            get_merged_extent(&table.rows, ri, ci)
+        } else {
+            (cell.rowspan as usize, cell.colspan as usize)
+        };
+
+        let tag = if cell.is_header { "th" } else { "td" };
+        out.push('<');
+        out.push_str(tag);
+        if rowspan != 1 {
+            out.push_str(&format!(" rowspan=\"{}\"", rowspan));
+        }
+        if colspan != 1 {
+            out.push_str(&format!(" colspan=\"{}\"", colspan));
+        }
+        write_attrs(out, &cell.attrs, &["rowspan", "colspan"]);
+        out.push('>');
+        out.push_str(&cell.value.to_html(false, true));
+        out.push_str("</");
+        out.push_str(tag);
+        out.push('>');
+    }
+    out.push_str("</tr>");
+}
+
+/// Number of rows and columns covered by a cell of a spanned table and its copies
+fn get_merged_extent(rows: &[Row], ri: usize, ci: usize) -> (usize, usize) {
+    let origin = rows[ri].cells[ci].origin;
+    let is_copy = |cell: Option<&Cell>| cell.is_some_and(|c| c.is_span_copy && c.origin == origin);
+
+    let colspan = 1
+        + (ci + 1..rows[ri].cells.len())
+            .take_while(|&cj| is_copy(rows[ri].cells.get(cj)))
+            .count();
+    let rowspan = 1
+        + (ri + 1..rows.len())
+            .take_while(|&rj| is_copy(rows[rj].cells.get(ci)))
+            .count();
+    (rowspan, colspan)
+}
+
+/// Write attributes sorted by name, skipping the ignored ones
+fn write_attrs(out: &mut String, attrs: &HashMap<String, String>, ignored_attrs: &[&str]) {
+    let mut names = attrs
+        .keys()
+        .filter(|name| !ignored_attrs.contains(&name.as_str()))
+        .collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        out.push(' ');
+        out.push_str(name);
+        out.push_str("=\"");
+        out.push_str(&escape_html(&attrs[name]));
+        out.push('"');
+    }
+}
+
+fn write_field(out: &mut String, field: &str, delimiter: char) {
+    if field.contains([delimiter, '"', '\r', '\n']) {
+        out.push('"');
+        out.push_str(&field.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(field);
+    }
+}
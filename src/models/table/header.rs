@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use super::{Cell, Row, RowSection, Table, ValueParser, ValueType};
+
+/// Number of header rows (at the top) and header columns (on the left) of a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
+pub struct TableHeader {
+    pub n_rows: usize,
+    pub n_cols: usize,
+}
+
+impl TableHeader {
+    /// Infer the header rows and header columns of a table. The table is expected to be spanned.
+    ///
+    /// A row is a header row if it is in `thead`, if its cells are headers (`th`, `scope="col"`
+    /// or bold/strong-only content), or if its cells are text while the cells below them are
+    /// numbers. Rows below the first row made of a single cell spanning the whole table are
+    /// labels of the body rows, not headers.
+    ///
+    /// A column is a header column if its cells below the header rows are headers (`th`,
+    /// `scope="row"` or bold/strong-only content). Header rows and columns are detected from
+    /// the top and the left, and there is always at least one body row and column.
+    ///
+    /// Numbers are parsed with the default [`ValueParser`], see [`TableHeader::detect_with_parser`].
+    pub fn detect(table: &Table) -> TableHeader {
+        TableHeader::detect_with_parser(table, &ValueParser::default())
+    }
+
+    /// Infer the header rows and header columns of a table (see [`TableHeader::detect`]), with
+    /// the numbers of the cells parsed by `parser`
+    pub fn detect_with_parser(table: &Table, parser: &ValueParser) -> TableHeader {
+        let n_rows = table.rows.len();
+        let mut n_header_rows = 0;
+        while n_header_rows + 1 < n_rows && is_header_row(table, n_header_rows, parser) {
+            n_header_rows += 1;
+        }
+
+        let n_cols = table.rows.iter().map(|r| r.cells.len()).max().unwrap_or(0);
+        let mut n_header_cols = 0;
+        while n_header_cols + 1 < n_cols && is_header_col(table, n_header_rows, n_header_cols) {
+            n_header_cols += 1;
+        }
+
+        TableHeader {
+            n_rows: n_header_rows,
+            n_cols: n_header_cols,
+        }
+    }
+}
+
+/// Separator of the header texts in the names of the columns of exported tables
+pub const COLUMN_NAME_SEPARATOR: &str = " / ";
+
+/// A table whose header rows are flattened into one name per column
+#[derive(Debug, Clone)]
+pub struct FlattenedTable {
+    // texts of the header cells of each column from the top to the bottom, without empty texts
+    // and without repeated texts of cells spanning multiple header rows
+    pub column_paths: Vec<Vec<String>>,
+    // rows below the header rows
+    pub rows: Vec<Row>,
+}
+
+impl FlattenedTable {
+    /// Flatten the header rows of a table. The table is expected to be spanned.
+    pub fn new(table: &Table, n_header_rows: usize) -> FlattenedTable {
+        let n_header_rows = n_header_rows.min(table.rows.len());
+        let n_cols = table.rows.iter().map(|r| r.cells.len()).max().unwrap_or(0);
+
+        let column_paths = (0..n_cols)
+            .map(|ci| {
+                let mut path: Vec<String> = vec![];
+                for row in &table.rows[..n_header_rows] {
+                    let text = match row.cells.get(ci) {
+                        None => continue,
+                        Some(cell) => cell.value.text.trim(),
+                    };
+                    if !text.is_empty() && path.last().map(|s| s.as_str()) != Some(text) {
+                        path.push(text.to_owned());
+                    }
+                }
+                path
+            })
+            .collect();
+
+        FlattenedTable {
+            column_paths,
+            rows: table.rows[n_header_rows..].to_vec(),
+        }
+    }
+
+    /// Name of each column, made by joining its header texts with the separator
+    pub fn column_names(&self, separator: &str) -> Vec<String> {
+        self.column_paths
+            .iter()
+            .map(|path| path.join(separator))
+            .collect()
+    }
+
+    pub fn to_list(&self) -> Vec<Vec<String>> {
+        self.rows.iter().map(|r| r.to_list()).collect()
+    }
+}
+
+fn is_header_row(table: &Table, ri: usize, parser: &ValueParser) -> bool {
+    let row = &table.rows[ri];
+    if row.section == RowSection::THead {
+        return true;
+    }
+
+    let cells = row
+        .cells
+        .iter()
+        .filter(|c| !is_empty_cell(c))
+        .collect::<Vec<_>>();
+    if cells.is_empty() {
+        return false;
+    }
+
+    // a row made of a single cell spanning the whole table below the first row is a label
+    // of the rows below it, not a header
+    if ri > 0 && row.cells.len() > 1 && is_single_merged_cell(&row.cells) {
+        return false;
+    }
+
+    if cells.iter().all(|c| is_header_cell(c, "col")) {
+        return true;
+    }
+
+    // type difference: the row is all text while the rows below are mostly numbers in
+    // at least one of its columns
+    if cells.iter().any(|c| is_number(parser, &c.value.text)) {
+        return false;
+    }
+    (0..row.cells.len()).any(|ci| {
+        if is_empty_cell(&row.cells[ci]) {
+            return false;
+        }
+        let below = table.rows[ri + 1..]
+            .iter()
+            .filter_map(|r| r.cells.get(ci))
+            .filter(|c| !is_empty_cell(c))
+            .collect::<Vec<_>>();
+        !below.is_empty()
+            && below
+                .iter()
+                .filter(|c| is_number(parser, &c.value.text))
+                .count()
+                * 2
+                > below.len()
+    })
+}
+
+/// Whether the cells are copies of the same merged cell
+fn is_single_merged_cell(cells: &[Cell]) -> bool {
+    let first = &cells[0];
+    cells[1..].iter().all(|c| match (first.origin, c.origin) {
+        (Some(o1), Some(o2)) => o1 == o2,
+        _ => c.value.text == first.value.text,
+    })
+}
+
+fn is_header_col(table: &Table, n_header_rows: usize, ci: usize) -> bool {
+    let cells = table.rows[n_header_rows..]
+        .iter()
+        .filter_map(|r| r.cells.get(ci))
+        .filter(|c| !is_empty_cell(c))
+        .collect::<Vec<_>>();
+    !cells.is_empty() && cells.iter().all(|c| is_header_cell(c, "row"))
+}
+
+/// Whether a cell is marked as header. `direction` is either "row" or "col": a header
+/// cell with a scope in the other direction is not a header of this direction.
+fn is_header_cell(cell: &Cell, direction: &str) -> bool {
+    match cell
+        .attrs
+        .get("scope")
+        .map(|s| s.trim().to_ascii_lowercase())
+    {
+        Some(scope) if scope == direction || scope == format!("{}group", direction) => return true,
+        Some(scope) if scope == "row" || scope == "col" || scope.ends_with("group") => {
+            return false
+        }
+        _ => {}
+    }
+    cell.is_header || is_bold_only(cell)
+}
+
+fn is_empty_cell(cell: &Cell) -> bool {
+    cell.value.text.trim().is_empty()
+}
+
+/// Whether every visible character of the cell is inside a `b` or `strong` element
+fn is_bold_only(cell: &Cell) -> bool {
+    let text = &cell.value;
+    let bold_ranges = text
+        .iter_element_id()
+        .map(|id| text.element.get_node(id))
+        .filter(|el| el.tag == "b" || el.tag == "strong")
+        .map(|el| (el.start, el.end))
+        .collect::<Vec<_>>();
+    if bold_ranges.is_empty() {
+        return false;
+    }
+
+    text.text
+        .char_indices()
+        .filter(|(_, c)| !c.is_whitespace())
+        .all(|(i, _)| {
+            bold_ranges
+                .iter()
+                .any(|(start, end)| *start <= i && i < *end)
+        })
+}
+
+/// Whether the text is a number, a percentage or a quantity (e.g., an amount of money)
+fn is_number(parser: &ValueParser, text: &str) -> bool {
+    matches!(
+        parser.parse(text).value_type,
+        ValueType::Number | ValueType::Percentage | ValueType::Quantity
+    )
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl TableHeader {
+    #[new]
+    fn py_new(n_rows: usize, n_cols: usize) -> Self {
+        TableHeader { n_rows, n_cols }
+    }
+
+    #[getter]
+    fn n_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    #[getter]
+    fn n_cols(&self) -> usize {
+        self.n_cols
+    }
+}
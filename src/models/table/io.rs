@@ -0,0 +1,272 @@
+//! Reading and writing files of tables, either as newline-delimited JSON (one table per
+//! line) or as records of the binary format of tables ([`Table::to_bytes`]), each prefixed by
+//! its length as a little-endian u64.
+//!
+//! Tables are read lazily, one at a time, so that files larger than memory can be processed.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use super::Table;
+use crate::error::RSoupError;
+
+#[cfg(feature = "python")]
+use super::PyTable;
+#[cfg(feature = "python")]
+use crate::error::into_pyerr;
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// Format of a file of tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableFormat {
+    /// one JSON table per line
+    #[default]
+    JsonLines,
+    /// tables in the binary format, each prefixed by its length in bytes as a little-endian u64
+    Postcard,
+}
+
+impl TableFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TableFormat::JsonLines => "jsonl",
+            TableFormat::Postcard => "postcard",
+        }
+    }
+}
+
+impl FromStr for TableFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(TableFormat::JsonLines),
+            "postcard" => Ok(TableFormat::Postcard),
+            _ => Err(format!("Invalid table format: {}", s)),
+        }
+    }
+}
+
+/// Write tables one by one to a file
+pub struct TableWriter<W: Write> {
+    writer: W,
+    format: TableFormat,
+}
+
+impl TableWriter<BufWriter<File>> {
+    /// Create (or truncate) the file at `path` and write tables to it
+    pub fn create<P: AsRef<Path>>(path: P, format: TableFormat) -> Result<Self, RSoupError> {
+        Ok(TableWriter::new(
+            BufWriter::new(File::create(path)?),
+            format,
+        ))
+    }
+}
+
+impl<W: Write> TableWriter<W> {
+    pub fn new(writer: W, format: TableFormat) -> Self {
+        TableWriter { writer, format }
+    }
+
+    pub fn write(&mut self, table: &Table) -> Result<(), RSoupError> {
+        match self.format {
+            TableFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, table)?;
+                self.writer.write_all(b"\n")?;
+            }
+            TableFormat::Postcard => {
+                let bytes = table.to_bytes()?;
+                self.writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+                self.writer.write_all(&bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), RSoupError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Flush the written tables and return the underlying writer
+    pub fn into_inner(mut self) -> Result<W, RSoupError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Iterate over the tables of a file, reading one table at a time
+pub struct TableReader<R: BufRead> {
+    reader: R,
+    format: TableFormat,
+    buffer: Vec<u8>,
+}
+
+impl TableReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P, format: TableFormat) -> Result<Self, RSoupError> {
+        Ok(TableReader::new(BufReader::new(File::open(path)?), format))
+    }
+}
+
+impl<R: BufRead> TableReader<R> {
+    pub fn new(reader: R, format: TableFormat) -> Self {
+        TableReader {
+            reader,
+            format,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Read the next table, returns None at the end of the file
+    pub fn read(&mut self) -> Result<Option<Table>, RSoupError> {
+        match self.format {
+            TableFormat::JsonLines => loop {
+                self.buffer.clear();
+                if self.reader.read_until(b'\n', &mut self.buffer)? == 0 {
+                    return Ok(None);
+                }
+                // skip blank lines
+                if self.buffer.iter().all(|b| b.is_ascii_whitespace()) {
+                    continue;
+                }
+                return Ok(Some(serde_json::from_slice(&self.buffer)?));
+            },
+            TableFormat::Postcard => {
+                let mut length = [0u8; 8];
+                // the end of the file is only valid at the boundary of a record
+                if self.reader.fill_buf()?.is_empty() {
+                    return Ok(None);
+                }
+                self.reader.read_exact(&mut length)?;
+                let length = u64::from_le_bytes(length);
+                // the buffer grows with the bytes that are read, not with the length of the
+                // record, which cannot be trusted
+                self.buffer.clear();
+                let n_bytes = self
+                    .reader
+                    .by_ref()
+                    .take(length)
+                    .read_to_end(&mut self.buffer)?;
+                if n_bytes as u64 != length {
+                    return Err(std::io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        format!("truncated record: {} of {} bytes", n_bytes, length),
+                    )
+                    .into());
+                }
+                Ok(Some(Table::from_bytes(&self.buffer)?))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for TableReader<R> {
+    type Item = Result<Table, RSoupError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+/// Write tables one by one to a file, use it as a context manager or call `close` at the end
+#[cfg(feature = "python")]
+#[pyclass(module = "rsoup.core", name = "TableWriter")]
+pub struct PyTableWriter {
+    writer: Option<TableWriter<BufWriter<File>>>,
+}
+
+#[cfg(feature = "python")]
+impl PyTableWriter {
+    fn get_writer(&mut self) -> PyResult<&mut TableWriter<BufWriter<File>>> {
+        self.writer
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("I/O operation on closed TableWriter"))
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyTableWriter {
+    #[new]
+    #[args(format = "\"jsonl\"")]
+    fn new(path: &str, format: &str) -> PyResult<Self> {
+        let format = format
+            .parse::<TableFormat>()
+            .map_err(PyValueError::new_err)?;
+        Ok(PyTableWriter {
+            writer: Some(TableWriter::create(path, format).map_err(into_pyerr)?),
+        })
+    }
+
+    fn write(&mut self, table: PyRef<PyTable>) -> PyResult<()> {
+        self.get_writer()?.write(&table.table).map_err(into_pyerr)
+    }
+
+    fn write_many(&mut self, tables: Vec<PyRef<PyTable>>) -> PyResult<()> {
+        let writer = self.get_writer()?;
+        for table in tables {
+            writer.write(&table.table).map_err(into_pyerr)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> PyResult<()> {
+        self.get_writer()?.flush().map_err(into_pyerr)
+    }
+
+    /// Flush and close the file, does nothing if the file is already closed
+    fn close(&mut self) -> PyResult<()> {
+        match self.writer.take() {
+            Some(writer) => writer.into_inner().map(|_| ()).map_err(into_pyerr),
+            None => Ok(()),
+        }
+    }
+
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) -> PyResult<()> {
+        self.close()
+    }
+}
+
+/// Iterate over the tables of a file, reading one table at a time
+#[cfg(feature = "python")]
+#[pyclass(module = "rsoup.core", name = "TableReader")]
+pub struct PyTableReader {
+    reader: TableReader<BufReader<File>>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyTableReader {
+    #[new]
+    #[args(format = "\"jsonl\"")]
+    fn new(path: &str, format: &str) -> PyResult<Self> {
+        let format = format
+            .parse::<TableFormat>()
+            .map_err(PyValueError::new_err)?;
+        Ok(PyTableReader {
+            reader: TableReader::open(path, format).map_err(into_pyerr)?,
+        })
+    }
+
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<PyTable>> {
+        Ok(self.reader.read().map_err(into_pyerr)?.map(PyTable::from))
+    }
+}
@@ -0,0 +1,156 @@
+//! Layouts of tables from older versions of the binary format, see [`crate::models::format`].
+
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+use super::{Cell, CellOrigin, Column, Row, RowSection, Table, TableHeader};
+use crate::models::{content_hierarchy::ContentHierarchy, rich_text::RichText};
+
+/// Layout of tables in version 1, before rows had sections and cells had nested tables or
+/// provenance
+#[derive(Deserialize)]
+pub struct TableV1 {
+    pub id: String,
+    pub url: String,
+    pub caption: String,
+    pub attrs: HashMap<String, String>,
+    pub context: Vec<ContentHierarchy>,
+    pub rows: Vec<RowV1>,
+}
+
+#[derive(Deserialize)]
+pub struct RowV1 {
+    pub cells: Vec<CellV1>,
+    pub attrs: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+pub struct CellV1 {
+    pub is_header: bool,
+    pub rowspan: u16,
+    pub colspan: u16,
+    pub attrs: HashMap<String, String>,
+    pub value: RichText,
+}
+
+impl From<TableV1> for Table {
+    fn from(table: TableV1) -> Self {
+        Table::new(
+            table.id,
+            table.url,
+            table.caption,
+            table.attrs,
+            table.context,
+            table.rows.into_iter().map(Row::from).collect(),
+        )
+    }
+}
+
+impl From<RowV1> for Row {
+    fn from(row: RowV1) -> Self {
+        Row::new(row.cells.into_iter().map(Cell::from).collect(), row.attrs)
+    }
+}
+
+impl From<CellV1> for Cell {
+    fn from(cell: CellV1) -> Self {
+        Cell::new(
+            cell.is_header,
+            cell.rowspan,
+            cell.colspan,
+            cell.attrs,
+            cell.value,
+        )
+    }
+}
+
+/// Layout of tables in version 2, before rows had the index of their row group. Rows and cells
+/// of this layout are versions 1 of rows and cells.
+#[derive(Deserialize)]
+pub struct TableV2 {
+    pub id: String,
+    pub url: String,
+    pub caption: String,
+    pub attrs: HashMap<String, String>,
+    pub context: Vec<ContentHierarchy>,
+    pub rows: Vec<RowV2>,
+    pub parent_id: Option<String>,
+    pub columns: Vec<Column>,
+    pub header: Option<TableHeader>,
+}
+
+#[derive(Deserialize)]
+pub struct RowV2 {
+    pub cells: Vec<CellV2>,
+    pub attrs: HashMap<String, String>,
+    pub section: RowSection,
+}
+
+#[derive(Deserialize)]
+pub struct CellV2 {
+    pub is_header: bool,
+    pub rowspan: u16,
+    pub colspan: u16,
+    pub attrs: HashMap<String, String>,
+    pub value: RichText,
+    pub nested_tables: Vec<TableV2>,
+    pub origin: Option<CellOrigin>,
+    pub is_span_copy: bool,
+}
+
+impl From<TableV2> for Table {
+    fn from(table: TableV2) -> Self {
+        // row groups were the runs of consecutive rows of the same section
+        let mut group = 0;
+        let mut prev_section = None;
+        let mut rows = Vec::with_capacity(table.rows.len());
+        for row in table.rows {
+            if prev_section.is_some_and(|section| section != row.section) {
+                group += 1;
+            }
+            prev_section = Some(row.section);
+
+            let mut row = Row::from(row);
+            row.group = group;
+            rows.push(row);
+        }
+
+        Table {
+            id: table.id,
+            url: table.url,
+            caption: table.caption,
+            attrs: table.attrs,
+            context: table.context,
+            rows,
+            parent_id: table.parent_id,
+            columns: table.columns,
+            header: table.header,
+        }
+    }
+}
+
+impl From<RowV2> for Row {
+    fn from(row: RowV2) -> Self {
+        Row {
+            cells: row.cells.into_iter().map(Cell::from).collect(),
+            attrs: row.attrs,
+            section: row.section,
+            group: 0,
+        }
+    }
+}
+
+impl From<CellV2> for Cell {
+    fn from(cell: CellV2) -> Self {
+        Cell {
+            is_header: cell.is_header,
+            rowspan: cell.rowspan,
+            colspan: cell.colspan,
+            attrs: cell.attrs,
+            value: cell.value,
+            nested_tables: cell.nested_tables.into_iter().map(Table::from).collect(),
+            origin: cell.origin,
+            is_span_copy: cell.is_span_copy,
+        }
+    }
+}
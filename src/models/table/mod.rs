@@ -1,9 +1,33 @@
 pub mod cell;
+#[cfg(feature = "python")]
 pub mod cell_iter;
+pub mod column;
+pub mod export;
+pub mod header;
+pub mod io;
+mod legacy;
+#[cfg(feature = "arrow")]
+pub mod record_batch;
 pub mod row;
+#[cfg(feature = "python")]
 pub mod row_iter;
+#[allow(clippy::module_inception)]
 pub mod table;
+pub mod value;
 
-pub use self::cell::Cell;
-pub use self::row::Row;
-pub use self::table::Table;
+pub use self::cell::{Cell, CellOrigin};
+pub use self::column::Column;
+pub use self::export::CellFormat;
+pub use self::header::{FlattenedTable, TableHeader, COLUMN_NAME_SEPARATOR};
+pub use self::io::{TableFormat, TableReader, TableWriter};
+pub use self::row::{Row, RowSection};
+pub use self::table::{SpanMode, Table, MAX_COLSPAN, MAX_ROWSPAN};
+pub use self::value::{Date, TypedValue, ValueParser, ValueType};
+
+#[cfg(feature = "python")]
+pub use self::{
+    cell::PyCell,
+    io::{PyTableReader, PyTableWriter},
+    row::PyRow,
+    table::PyTable,
+};
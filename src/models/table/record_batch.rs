@@ -0,0 +1,151 @@
+//! Conversion of tables to Apache Arrow record batches and Parquet files, in a long format
+//! with one record per cell: `table_id`, `row`, `col`, `text`, `is_header`, `rowspan`,
+//! `colspan` and `html` (the inner HTML of the cell).
+
+use std::{borrow::Borrow, io::Write, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, BooleanBuilder, StringBuilder, UInt16Builder, UInt32Builder},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    record_batch::RecordBatch,
+};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    file::properties::WriterProperties,
+};
+
+use super::Table;
+use crate::error::RSoupError;
+
+#[cfg(feature = "python")]
+use super::PyTable;
+#[cfg(feature = "python")]
+use crate::error::into_pyerr;
+#[cfg(feature = "python")]
+use arrow::{
+    array::{Array, StructArray},
+    ffi::{FFI_ArrowArray, FFI_ArrowSchema},
+};
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+/// Number of tables written in each record batch of a Parquet file
+const PARQUET_BATCH_SIZE: usize = 1024;
+
+/// Schema of the record batches of tables
+pub fn get_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("table_id", DataType::Utf8, false),
+        Field::new("row", DataType::UInt32, false),
+        Field::new("col", DataType::UInt32, false),
+        Field::new("text", DataType::Utf8, false),
+        Field::new("is_header", DataType::Boolean, false),
+        Field::new("rowspan", DataType::UInt16, false),
+        Field::new("colspan", DataType::UInt16, false),
+        Field::new("html", DataType::Utf8, false),
+    ]))
+}
+
+/// Convert tables to a record batch with one record per cell, in the order of the tables,
+/// rows and cells.
+///
+/// `row` and `col` are the position of the cell in `Table.rows` and `Row.cells`. For cells of
+/// a spanned table, `rowspan` and `colspan` are the spans of the merged cell they come from.
+pub fn to_record_batch<T: Borrow<Table>>(tables: &[T]) -> Result<RecordBatch, RSoupError> {
+    let n_cells = tables
+        .iter()
+        .map(Borrow::borrow)
+        .flat_map(|t| t.rows.iter())
+        .map(|r| r.cells.len())
+        .sum();
+
+    let mut table_ids = StringBuilder::with_capacity(n_cells, n_cells * 16);
+    let mut row_indices = UInt32Builder::with_capacity(n_cells);
+    let mut col_indices = UInt32Builder::with_capacity(n_cells);
+    let mut texts = StringBuilder::with_capacity(n_cells, n_cells * 16);
+    let mut is_headers = BooleanBuilder::with_capacity(n_cells);
+    let mut rowspans = UInt16Builder::with_capacity(n_cells);
+    let mut colspans = UInt16Builder::with_capacity(n_cells);
+    let mut htmls = StringBuilder::with_capacity(n_cells, n_cells * 32);
+
+    for table in tables.iter().map(Borrow::borrow) {
+        for (ri, row) in table.rows.iter().enumerate() {
+            for (ci, cell) in row.cells.iter().enumerate() {
+                let (rowspan, colspan) = match cell.origin {
+                    Some(origin) => (origin.rowspan, origin.colspan),
+                    None => (cell.rowspan, cell.colspan),
+                };
+                table_ids.append_value(&table.id);
+                row_indices.append_value(ri as u32);
+                col_indices.append_value(ci as u32);
+                texts.append_value(&cell.value.text);
+                is_headers.append_value(cell.is_header);
+                rowspans.append_value(rowspan);
+                colspans.append_value(colspan);
+                htmls.append_value(cell.value.to_html(false, true));
+            }
+        }
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(table_ids.finish()),
+        Arc::new(row_indices.finish()),
+        Arc::new(col_indices.finish()),
+        Arc::new(texts.finish()),
+        Arc::new(is_headers.finish()),
+        Arc::new(rowspans.finish()),
+        Arc::new(colspans.finish()),
+        Arc::new(htmls.finish()),
+    ];
+    Ok(RecordBatch::try_new(get_schema(), columns)?)
+}
+
+/// Write tables to a Parquet file (zstd compressed) in the format of [`to_record_batch`]
+pub fn write_parquet<T: Borrow<Table>, W: Write + Send>(
+    tables: &[T],
+    writer: W,
+) -> Result<(), RSoupError> {
+    let props = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let mut writer = ArrowWriter::try_new(writer, get_schema(), Some(props))?;
+    for chunk in tables.chunks(PARQUET_BATCH_SIZE) {
+        writer.write(&to_record_batch(chunk)?)?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+/// Convert tables to a `pyarrow.RecordBatch` with one record per cell. The data is handed to
+/// pyarrow through the Arrow C data interface without being copied.
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn tables_to_arrow(py: Python, tables: Vec<PyRef<PyTable>>) -> PyResult<PyObject> {
+    let tables = tables.iter().map(|table| &table.table).collect::<Vec<_>>();
+    let batch = to_record_batch(&tables).map_err(into_pyerr)?;
+
+    let array = FFI_ArrowArray::new(&StructArray::from(batch).to_data());
+    let schema = FFI_ArrowSchema::try_from(get_schema().as_ref())
+        .map_err(|err| into_pyerr(RSoupError::from(err)))?;
+
+    // pyarrow moves the content of the structs, which are released when they are dropped
+    let record_batch = py.import("pyarrow")?.getattr("RecordBatch")?.call_method1(
+        "_import_from_c",
+        (
+            &array as *const FFI_ArrowArray as usize,
+            &schema as *const FFI_ArrowSchema as usize,
+        ),
+    )?;
+    Ok(record_batch.into())
+}
+
+/// Write tables to a Parquet file with one record per cell
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(name = "write_parquet")]
+pub fn py_write_parquet(tables: Vec<PyRef<PyTable>>, outfile: &str) -> PyResult<()> {
+    let tables = tables.iter().map(|table| &table.table).collect::<Vec<_>>();
+    let file = std::fs::File::create(outfile)?;
+    write_parquet(&tables, file).map_err(into_pyerr)
+}
@@ -1,78 +1,310 @@
+use std::str::FromStr;
+
 use hashbrown::HashMap;
-use pyo3::{exceptions::PyKeyError, prelude::*, types::PyDict};
 use serde::{Deserialize, Serialize};
-use std::fmt;
 
-use super::Cell;
+use super::{legacy::RowV2, Cell};
+use crate::error::RSoupError;
+use crate::models::format::{self, DataKind};
+
+#[cfg(feature = "python")]
+use super::{PyCell, PyTable};
+#[cfg(feature = "python")]
+use crate::error::into_pyerr;
+#[cfg(feature = "python")]
+use pyo3::{
+    exceptions::{PyIndexError, PyKeyError, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+
+/// The row group (`thead`, `tbody` or `tfoot`) that a row belongs to.
+///
+/// Rows that are direct children of the table belong to `tbody` as browsers render them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum RowSection {
+    THead,
+    #[default]
+    TBody,
+    TFoot,
+}
+
+impl RowSection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RowSection::THead => "thead",
+            RowSection::TBody => "tbody",
+            RowSection::TFoot => "tfoot",
+        }
+    }
+}
+
+impl FromStr for RowSection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "thead" => Ok(RowSection::THead),
+            "tbody" => Ok(RowSection::TBody),
+            "tfoot" => Ok(RowSection::TFoot),
+            _ => Err(format!("Invalid row section: {}", s)),
+        }
+    }
+}
 
-#[derive(Clone, Deserialize, Serialize)]
-#[pyclass(module = "rsoup.core")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Row {
-    #[pyo3(get)]
-    pub cells: Vec<Py<Cell>>,
-    #[pyo3(get)]
+    pub cells: Vec<Cell>,
     pub attrs: HashMap<String, String>,
+    #[serde(default)]
+    pub section: RowSection,
+    // index of the row group (`thead`, `tbody` or `tfoot` element) of the row in the table,
+    // consecutive rows of the same section can belong to different groups
+    #[serde(default)]
+    pub group: usize,
 }
 
-#[pymethods]
 impl Row {
-    #[new]
-    pub fn new(cells: Vec<Py<Cell>>, attrs: HashMap<String, String>) -> Self {
-        Row { cells, attrs }
+    pub fn new(cells: Vec<Cell>, attrs: HashMap<String, String>) -> Self {
+        Row {
+            cells,
+            attrs,
+            section: RowSection::TBody,
+            group: 0,
+        }
     }
 
-    fn get_cell(&self, py: Python, ci: usize) -> PyResult<Py<Cell>> {
-        if ci >= self.cells.len() {
-            return Err(PyKeyError::new_err(format!(
-                "Key {} is out of cells' range [0, {})",
-                ci,
-                self.cells.len()
-            )));
+    /// Whether the two rows belong to the same row group. Rows of different sections are never
+    /// in the same group, even if they have the same group index (e.g., rows built by hand).
+    pub fn is_same_group(&self, other: &Row) -> bool {
+        self.group == other.group && self.section == other.section
+    }
+
+    pub fn get_cell(&self, ci: usize) -> Option<&Cell> {
+        self.cells.get(ci)
+    }
+
+    /// Encode in the versioned binary format, see [`crate::models::format`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RSoupError> {
+        format::encode(DataKind::Row, self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Row, RSoupError> {
+        let (version, payload) = format::decode_header(DataKind::Row, bytes)?;
+        match version {
+            1 => Ok(format::decode_postcard::<RowV2>(DataKind::Row, version, payload)?.into()),
+            _ => format::decode_postcard(DataKind::Row, version, payload),
         }
+    }
 
-        Ok(self.cells[ci].clone_ref(py))
+    pub fn to_json(&self) -> Result<String, RSoupError> {
+        Ok(serde_json::to_string(self)?)
     }
 
-    fn iter_cells(slf: Py<Row>, py: Python) -> super::cell_iter::CellRIter {
-        super::cell_iter::CellRIter {
-            row: slf.clone_ref(py),
-            cell_index: 0,
+    pub fn from_json(dat: &str) -> Result<Row, RSoupError> {
+        Ok(serde_json::from_str(dat)?)
+    }
+
+    pub fn to_list(&self) -> Vec<String> {
+        self.cells.iter().map(|c| c.value.text.clone()).collect()
+    }
+}
+
+/// Python wrapper of [`Row`]. A row obtained from a table is a view of the row in the table:
+/// reading or modifying it reads or modifies the table.
+#[cfg(feature = "python")]
+#[pyclass(module = "rsoup.core", name = "Row")]
+pub struct PyRow {
+    data: RowData,
+}
+
+#[cfg(feature = "python")]
+enum RowData {
+    Owned(Row),
+    InTable { table: Py<PyTable>, index: usize },
+}
+
+#[cfg(feature = "python")]
+impl From<Row> for PyRow {
+    fn from(row: Row) -> Self {
+        PyRow {
+            data: RowData::Owned(row),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl PyRow {
+    /// View of the row at `index` of the table
+    pub fn in_table(table: Py<PyTable>, index: usize) -> Self {
+        PyRow {
+            data: RowData::InTable { table, index },
         }
     }
 
-    pub(super) fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+    pub fn with_row<R>(&self, py: Python, f: impl FnOnce(&Row) -> R) -> PyResult<R> {
+        match &self.data {
+            RowData::Owned(row) => Ok(f(row)),
+            RowData::InTable { table, index } => table
+                .try_borrow(py)?
+                .table
+                .rows
+                .get(*index)
+                .map(f)
+                .ok_or_else(|| removed_error("row", *index)),
+        }
+    }
+
+    pub fn with_row_mut<R>(&mut self, py: Python, f: impl FnOnce(&mut Row) -> R) -> PyResult<R> {
+        match &mut self.data {
+            RowData::Owned(row) => Ok(f(row)),
+            RowData::InTable { table, index } => table
+                .try_borrow_mut(py)?
+                .table
+                .rows
+                .get_mut(*index)
+                .map(f)
+                .ok_or_else(|| removed_error("row", *index)),
+        }
+    }
+}
+
+/// Error of a view whose row or cell is no longer in its parent
+#[cfg(feature = "python")]
+pub(super) fn removed_error(kind: &str, index: usize) -> PyErr {
+    PyIndexError::new_err(format!(
+        "The {} at index {} is no longer in its parent",
+        kind, index
+    ))
+}
+
+#[cfg(feature = "python")]
+impl Row {
+    pub fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
         let o = PyDict::new(py);
 
         o.set_item("attrs", &self.attrs)?;
+        o.set_item("section", self.section.as_str())?;
+        o.set_item("group", self.group)?;
         o.set_item(
             "cells",
             &self
                 .cells
                 .iter()
-                .map(|c| c.borrow(py).to_dict(py))
+                .map(|c| c.to_dict(py))
                 .collect::<PyResult<Vec<_>>>()?,
         )?;
         Ok(o.into_py(py))
     }
+}
 
-    pub(super) fn to_list(&self, py: Python) -> Vec<String> {
-        self.cells
-            .iter()
-            .map(|c| c.borrow(py).value.borrow(py).text.clone())
-            .collect()
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyRow {
+    #[new]
+    #[args(section = "\"tbody\"", group = "0")]
+    pub fn new(
+        py: Python,
+        cells: Vec<PyRef<PyCell>>,
+        attrs: HashMap<String, String>,
+        section: &str,
+        group: usize,
+    ) -> PyResult<Self> {
+        Ok(PyRow::from(Row {
+            cells: cells
+                .iter()
+                .map(|c| c.with_cell(py, Cell::clone))
+                .collect::<PyResult<Vec<_>>>()?,
+            attrs,
+            section: section.parse().map_err(PyValueError::new_err)?,
+            group,
+        }))
+    }
+
+    #[getter]
+    fn cells(slf: PyRef<Self>, py: Python) -> PyResult<Vec<PyCell>> {
+        let n_cells = slf.with_row(py, |row| row.cells.len())?;
+        let slf: Py<Self> = slf.into();
+        Ok((0..n_cells)
+            .map(|ci| PyCell::in_row(slf.clone_ref(py), ci))
+            .collect())
+    }
+
+    #[getter]
+    fn attrs(&self, py: Python) -> PyResult<HashMap<String, String>> {
+        self.with_row(py, |row| row.attrs.clone())
+    }
+
+    #[getter]
+    fn section(&self, py: Python) -> PyResult<&'static str> {
+        self.with_row(py, |row| row.section.as_str())
+    }
+
+    #[setter]
+    fn set_section(&mut self, py: Python, section: &str) -> PyResult<()> {
+        let section = section.parse().map_err(PyValueError::new_err)?;
+        self.with_row_mut(py, |row| row.section = section)
+    }
+
+    #[getter]
+    fn group(&self, py: Python) -> PyResult<usize> {
+        self.with_row(py, |row| row.group)
+    }
+
+    #[setter]
+    fn set_group(&mut self, py: Python, group: usize) -> PyResult<()> {
+        self.with_row_mut(py, |row| row.group = group)
+    }
+
+    fn get_cell(slf: PyRef<Self>, py: Python, ci: usize) -> PyResult<PyCell> {
+        let n_cells = slf.with_row(py, |row| row.cells.len())?;
+        if ci >= n_cells {
+            return Err(PyKeyError::new_err(format!(
+                "Key {} is out of cells' range [0, {})",
+                ci, n_cells
+            )));
+        }
+
+        Ok(PyCell::in_row(slf.into(), ci))
+    }
+
+    fn iter_cells(slf: Py<PyRow>, py: Python) -> super::cell_iter::CellRIter {
+        super::cell_iter::CellRIter {
+            row: slf.clone_ref(py),
+            cell_index: 0,
+        }
+    }
+
+    pub fn to_json(&self, py: Python) -> PyResult<String> {
+        self.with_row(py, |row| row.to_json())?.map_err(into_pyerr)
+    }
+
+    #[staticmethod]
+    pub fn from_json(dat: &str) -> PyResult<PyRow> {
+        Ok(PyRow::from(Row::from_json(dat).map_err(into_pyerr)?))
+    }
+
+    pub fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let out = self
+            .with_row(py, |row| row.to_bytes())?
+            .map_err(into_pyerr)?;
+        Ok(PyBytes::new(py, &out))
+    }
+
+    #[staticmethod]
+    pub fn from_bytes(dat: &PyBytes) -> PyResult<PyRow> {
+        Ok(PyRow::from(
+            Row::from_bytes(dat.as_bytes()).map_err(into_pyerr)?,
+        ))
+    }
+
+    #[pyo3(name = "to_dict")]
+    fn py_to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+        self.with_row(py, |row| row.to_dict(py))?
     }
-}
 
-impl fmt::Debug for Row {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        Python::with_gil(|py| {
-            f.debug_struct("Row")
-                .field(
-                    "cells",
-                    &self.cells.iter().map(|l| l.borrow(py)).collect::<Vec<_>>(),
-                )
-                .field("attrs", &self.attrs)
-                .finish()
-        })
+    #[pyo3(name = "to_list")]
+    fn py_to_list(&self, py: Python) -> PyResult<Vec<String>> {
+        self.with_row(py, Row::to_list)
     }
 }
@@ -1,10 +1,10 @@
 use pyo3::prelude::*;
 
-use super::{Row, Table};
+use super::{PyRow, PyTable};
 
 #[pyclass(module = "rsoup.core", unsendable)]
 pub struct RowIter {
-    pub table: Py<Table>,
+    pub table: Py<PyTable>,
     pub row_index: usize,
 }
 
@@ -14,12 +14,11 @@ impl RowIter {
         slf
     }
 
-    fn __next__(&mut self, py: Python) -> Option<Py<Row>> {
-        let table = self.table.borrow(py);
-        if self.row_index >= table.rows.len() {
+    fn __next__(&mut self, py: Python) -> Option<PyRow> {
+        if self.row_index >= self.table.borrow(py).table.rows.len() {
             None
         } else {
-            let row = table.rows[self.row_index].clone_ref(py);
+            let row = PyRow::in_table(self.table.clone_ref(py), self.row_index);
             self.row_index += 1;
             Some(row)
         }
@@ -1,53 +1,94 @@
+use std::str::FromStr;
+
 use anyhow::Result;
 use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json;
+
+use super::column::expand_columns;
+use super::export;
+use super::legacy::{TableV1, TableV2};
+use super::{Cell, CellFormat, CellOrigin, Column, FlattenedTable, Row, TableHeader};
+use crate::error::RSoupError;
+use crate::models::{
+    content_hierarchy::ContentHierarchy,
+    format::{self, DataKind},
+    rich_text::RichText,
+};
+
+#[cfg(feature = "python")]
+use super::{PyCell, PyRow, TypedValue, ValueParser};
+#[cfg(feature = "python")]
+use crate::{error::into_pyerr, models::content_hierarchy::PyContentHierarchy};
+#[cfg(feature = "python")]
 use pyo3::{
-    exceptions::PyKeyError,
+    exceptions::{PyKeyError, PyValueError},
     prelude::*,
     types::{PyBytes, PyDict, PyString},
 };
-use serde::{Deserialize, Serialize};
-use serde_json;
-use std::fmt;
 
-use super::{Cell, Row};
-use crate::models::{content_hierarchy::ContentHierarchy, rich_text::RichText};
+/// Largest colspan and rowspan of cells in browsers, larger spans are clamped to them
+pub const MAX_COLSPAN: u16 = 1000;
+pub const MAX_ROWSPAN: u16 = 65534;
+
+/// Algorithm used to span a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpanMode {
+    /// [`Table::span`], raise an error on tables that it cannot handle
+    #[default]
+    Default,
+    /// [`Table::span_browser`], follow the HTML specification
+    Browser,
+}
+
+impl SpanMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpanMode::Default => "default",
+            SpanMode::Browser => "browser",
+        }
+    }
+}
+
+impl FromStr for SpanMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(SpanMode::Default),
+            "browser" => Ok(SpanMode::Browser),
+            _ => Err(format!("Invalid span mode: {}", s)),
+        }
+    }
+}
 
-#[derive(Clone, Deserialize, Serialize)]
-#[pyclass(module = "rsoup.core")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Table {
-    #[pyo3(get, set)]
     pub id: String,
-    #[pyo3(get, set)]
     pub url: String,
-    #[pyo3(get, set)]
     pub caption: String,
-    #[pyo3(get)]
     pub attrs: HashMap<String, String>,
-    #[pyo3(get)]
-    pub context: Vec<Py<ContentHierarchy>>,
-    #[pyo3(get)]
-    pub rows: Vec<Py<Row>>,
+    pub context: Vec<ContentHierarchy>,
+    pub rows: Vec<Row>,
+    // id of the table containing this table, if this table is nested in a cell of another table
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    // column descriptors from `<colgroup>` and `<col>` elements
+    #[serde(default)]
+    pub columns: Vec<Column>,
+    // header rows and columns, only available after running the header detection
+    #[serde(default)]
+    pub header: Option<TableHeader>,
 }
 
-#[pymethods]
 impl Table {
-    #[new]
-    #[args(
-        "*",
-        id = "String::new()",
-        url = "String::new()",
-        caption = "String::new()",
-        attrs = "HashMap::new()",
-        context = "Vec::new()",
-        rows = "Vec::new()"
-    )]
     pub fn new(
         id: String,
         url: String,
         caption: String,
         attrs: HashMap<String, String>,
-        context: Vec<Py<ContentHierarchy>>,
-        rows: Vec<Py<Row>>,
+        context: Vec<ContentHierarchy>,
+        rows: Vec<Row>,
     ) -> Self {
         Self {
             id,
@@ -56,12 +97,15 @@ impl Table {
             attrs,
             context,
             rows,
+            parent_id: None,
+            columns: Vec::new(),
+            header: None,
         }
     }
 
     /// Span the table by copying values to merged field
-    pub fn span(&self, py: Python) -> PyResult<Table> {
-        if self.rows.len() == 0 {
+    pub fn span(&self) -> Result<Table, RSoupError> {
+        if self.rows.is_empty() {
             return Ok(self.clone());
         }
 
@@ -76,11 +120,9 @@ impl Table {
         // so we may have to iterate several times
 
         let mut cols = vec![0; self.rows.len()];
-        for (i, py_row) in self.rows.iter().enumerate() {
-            let row = py_row.borrow(py);
+        for (i, row) in self.rows.iter().enumerate() {
             cols[i] += row.cells.len();
-            for py_cell in &row.cells {
-                let cell = py_cell.borrow(py);
+            for cell in &row.cells {
                 if cell.rowspan > 1 {
                     for j in 1..cell.rowspan {
                         if i + (j as usize) < cols.len() {
@@ -98,33 +140,50 @@ impl Table {
         // so I decide to not handle that. Hope that we don't have many tables like that.
         // >>> finish find the max #cols
 
-        for py_row in &self.rows {
-            let row = py_row.borrow(py);
+        for row in &self.rows {
             let mut new_row = Vec::with_capacity(row.cells.len());
             let mut pj = 0;
 
             for (cell_index, ocell) in row.cells.iter().enumerate() {
-                let mut cell = ocell.borrow(py).clone();
+                let mut cell = ocell.clone();
                 let original_colspan = cell.colspan;
                 let original_rowspan = cell.rowspan;
                 cell.colspan = 1;
                 cell.rowspan = 1;
 
                 // adding cell from the top
-                while pending_ops.contains_key(&(pi, pj)) {
-                    new_row.push(Py::new(py, pending_ops.remove(&(pi, pj)).unwrap())?);
+                while let Some(pending_cell) = pending_ops.remove(&(pi, pj)) {
+                    new_row.push(pending_cell);
                     pj += 1;
                 }
 
+                // keep the origin if the table has been spanned before
+                if cell.origin.is_none() {
+                    cell.origin = Some(CellOrigin {
+                        row: pi as usize,
+                        col: pj as usize,
+                        rowspan: original_rowspan,
+                        colspan: original_colspan,
+                    });
+                }
+
                 // now add cell and expand the column
+                let span_copy = cell.span_copy();
+                let mut cell = Some(cell);
                 for _ in 0..original_colspan {
                     if pending_ops.contains_key(&(pi, pj)) {
                         // exception, overlapping between colspan and rowspan
-                        return Err(crate::error::OverlapSpanPyError::new_err("".to_owned()).into());
+                        return Err(RSoupError::OverlapSpanError {
+                            table_id: self.id.clone(),
+                            row: pi as usize,
+                            col: cell_index,
+                            rowspan: original_rowspan,
+                            colspan: original_colspan,
+                        });
                     }
-                    new_row.push(Py::new(py, cell.clone())?);
+                    new_row.push(cell.take().unwrap_or_else(|| span_copy.clone()));
                     for ioffset in 1..original_rowspan {
-                        pending_ops.insert((pi + ioffset as i32, pj), cell.clone());
+                        pending_ops.insert((pi + ioffset as i32, pj), span_copy.clone());
                     }
                     pj += 1;
 
@@ -132,10 +191,13 @@ impl Table {
                         // our algorithm cannot handle the case where people are bullying the colspan system, and only can handle the case
                         // where the span that goes beyond the maximum number of columns is in the last column.
                         if cell_index != row.cells.len() - 1 {
-                            return Err(crate::error::InvalidCellSpanPyError::new_err(
-                                "".to_owned(),
-                            )
-                            .into());
+                            return Err(RSoupError::InvalidCellSpanError {
+                                table_id: self.id.clone(),
+                                row: pi as usize,
+                                col: cell_index,
+                                rowspan: original_rowspan,
+                                colspan: original_colspan,
+                            });
                         } else {
                             break;
                         }
@@ -144,27 +206,20 @@ impl Table {
             }
 
             // add more cells from the top since we reach the end
-            while pending_ops.contains_key(&(pi, pj)) && pj < max_ncols {
-                // println!(
-                //     "\tadding trailing pending ops: {:?}",
-                //     pending_ops.get(&(pi, pj)).unwrap()
-                // );
-                new_row.push(Py::new(py, pending_ops.remove(&(pi, pj)).unwrap())?);
+            while pj < max_ncols {
+                match pending_ops.remove(&(pi, pj)) {
+                    Some(pending_cell) => new_row.push(pending_cell),
+                    None => break,
+                }
                 pj += 1;
             }
 
-            // println!(
-            //     ">>> row {}\n\tnew_row: {:?}\n\tpending_ops: {:?}",
-            //     pi, new_row, pending_ops
-            // );
-
-            data.push(Py::new(
-                py,
-                Row {
-                    cells: new_row,
-                    attrs: row.attrs.clone(),
-                },
-            )?);
+            data.push(Row {
+                cells: new_row,
+                attrs: row.attrs.clone(),
+                section: row.section,
+                group: row.group,
+            });
             pi += 1;
         }
 
@@ -177,144 +232,280 @@ impl Table {
             attrs: self.attrs.clone(),
             context: self.context.clone(),
             rows: data,
+            parent_id: self.parent_id.clone(),
+            columns: expand_columns(&self.columns),
+            header: self.header,
         })
     }
 
+    /// Span the table with the given mode
+    pub fn span_with_mode(&self, mode: SpanMode) -> Result<Table, RSoupError> {
+        match mode {
+            SpanMode::Default => self.span(),
+            SpanMode::Browser => Ok(self.span_browser()),
+        }
+    }
+
+    /// Span the table following the WHATWG "forming a table" algorithm, which is how browsers
+    /// build the grid of a table. Unlike [`Table::span`], this never fails:
+    ///
+    /// * colspan is clamped to [1, [`MAX_COLSPAN`]] and rowspan to [0, [`MAX_ROWSPAN`]]
+    /// * rowspan = 0 spans to the end of the row group (see [`Row::is_same_group`])
+    /// * rowspan going beyond the row group is clamped to the end of the row group
+    /// * slots covered by overlapping cells keep the cell that was placed first
+    /// * slots that are not covered by any cell are filled with empty cells
+    pub fn span_browser(&self) -> Table {
+        let mut grid: Vec<Vec<Option<Cell>>> = vec![];
+        let mut grid_rows: Vec<Row> = vec![];
+        let mut xwidth = 0;
+        let mut ycurrent = 0;
+
+        let mut row_index = 0;
+        while row_index < self.rows.len() {
+            // each row group is processed separately
+            let first_row = &self.rows[row_index];
+            let (group, section) = (first_row.group, first_row.section);
+            let mut group_end = row_index;
+            while group_end < self.rows.len() && self.rows[group_end].is_same_group(first_row) {
+                group_end += 1;
+            }
+
+            // cells with rowspan = 0 of the group: (x, colspan, cell)
+            let mut downward_growing_cells: Vec<(usize, usize, Cell)> = vec![];
+
+            // cells cannot span beyond the rows of their group
+            let group_yend = ycurrent + (group_end - row_index);
+            for row in &self.rows[row_index..group_end] {
+                grid.push(vec![]);
+                grid_rows.push(Row {
+                    cells: vec![],
+                    attrs: row.attrs.clone(),
+                    section,
+                    group,
+                });
+            }
+
+            for row in &self.rows[row_index..group_end] {
+                grow_downward_growing_cells(&mut grid[ycurrent], &downward_growing_cells);
+
+                let mut xcurrent = 0;
+                for ocell in &row.cells {
+                    while xcurrent < grid[ycurrent].len() && grid[ycurrent][xcurrent].is_some() {
+                        xcurrent += 1;
+                    }
+
+                    let colspan = ocell.colspan.clamp(1, MAX_COLSPAN) as usize;
+                    let grows_downward = ocell.rowspan == 0;
+                    let rowspan = if grows_downward {
+                        1
+                    } else {
+                        (ocell.rowspan.min(MAX_ROWSPAN) as usize).min(group_yend - ycurrent)
+                    };
+                    xwidth = xwidth.max(xcurrent + colspan);
+
+                    let mut cell = ocell.clone();
+                    cell.rowspan = 1;
+                    cell.colspan = 1;
+                    // keep the origin if the table has been spanned before
+                    if cell.origin.is_none() {
+                        cell.origin = Some(CellOrigin {
+                            row: ycurrent,
+                            col: xcurrent,
+                            rowspan: ocell.rowspan,
+                            colspan: ocell.colspan,
+                        });
+                    }
+
+                    for (y, grid_row) in grid.iter_mut().enumerate().skip(ycurrent).take(rowspan) {
+                        for x in xcurrent..(xcurrent + colspan) {
+                            let copy = if y != ycurrent || x != xcurrent {
+                                cell.span_copy()
+                            } else {
+                                cell.clone()
+                            };
+                            set_empty_slot(grid_row, x, copy);
+                        }
+                    }
+
+                    if grows_downward {
+                        downward_growing_cells.push((xcurrent, colspan, cell.span_copy()));
+                    }
+                    xcurrent += colspan;
+                }
+                ycurrent += 1;
+            }
+            row_index = group_end;
+        }
+
+        // fill slots that are not covered by any cell
+        for (grid_row, row) in grid.into_iter().zip(grid_rows.iter_mut()) {
+            row.cells = grid_row
+                .into_iter()
+                .chain(std::iter::repeat(None))
+                .take(xwidth)
+                .map(|slot| {
+                    slot.unwrap_or_else(|| {
+                        Cell::new(false, 1, 1, HashMap::new(), RichText::empty())
+                    })
+                })
+                .collect();
+        }
+
+        Table {
+            id: self.id.clone(),
+            url: self.url.clone(),
+            caption: self.caption.clone(),
+            attrs: self.attrs.clone(),
+            context: self.context.clone(),
+            rows: grid_rows,
+            parent_id: self.parent_id.clone(),
+            columns: expand_columns(&self.columns),
+            header: self.header,
+        }
+    }
+
     /// Pad an irregular table (missing cells) to make it become a regular table
     ///
     /// This function only return new table when it's padded, otherwise, None.
-    pub fn pad(&self, py: Python) -> PyResult<Option<Table>> {
-        if self.rows.len() == 0 {
-            return Ok(None);
+    pub fn pad(&self) -> Option<Table> {
+        if self.rows.is_empty() {
+            return None;
         }
 
-        let borrowed_rows = self
-            .rows
-            .iter()
-            .map(|py_row| py_row.borrow(py))
-            .collect::<Vec<_>>();
-
-        let ncols = borrowed_rows[0].cells.len();
-        let is_regular_table = borrowed_rows.iter().all(|row| row.cells.len() == ncols);
+        let ncols = self.rows[0].cells.len();
+        let is_regular_table = self.rows.iter().all(|row| row.cells.len() == ncols);
         if is_regular_table {
-            return Ok(None);
+            return None;
         }
 
-        let max_ncols = borrowed_rows
-            .iter()
-            .map(|row| row.cells.len())
-            .max()
-            .unwrap();
+        let max_ncols = self.rows.iter().map(|row| row.cells.len()).max().unwrap();
         let default_cell = Cell {
             is_header: false,
             rowspan: 1,
             colspan: 1,
             attrs: HashMap::new(),
-            value: Py::new(py, RichText::empty())?,
+            value: RichText::empty(),
+            nested_tables: Vec::new(),
+            origin: None,
+            is_span_copy: false,
         };
 
         let mut rows = Vec::with_capacity(self.rows.len());
-        for r in borrowed_rows {
+        for r in &self.rows {
             let mut row = r.clone();
 
             let mut newcell = default_cell.clone();
             // heuristic to match header from the previous cell of the same row
-            newcell.is_header = row
-                .cells
-                .last()
-                .map_or(false, |cell| cell.borrow(py).is_header);
+            newcell.is_header = row.cells.last().is_some_and(|cell| cell.is_header);
 
             while row.cells.len() < max_ncols {
-                row.cells.push(Py::new(py, newcell.clone())?);
+                row.cells.push(newcell.clone());
             }
-            rows.push(Py::new(py, row)?);
+            rows.push(row);
         }
 
-        Ok(Some(Table {
+        Some(Table {
             id: self.id.clone(),
             url: self.url.clone(),
             caption: self.caption.clone(),
             attrs: self.attrs.clone(),
             context: self.context.clone(),
-            rows: rows,
-        }))
+            rows,
+            parent_id: self.parent_id.clone(),
+            columns: self.columns.clone(),
+            header: self.header,
+        })
+    }
+
+    /// Detect the header rows and columns of the table and store them in the table.
+    /// The table is expected to be spanned.
+    pub fn detect_headers(&mut self) -> TableHeader {
+        let header = TableHeader::detect(self);
+        self.header = Some(header);
+        header
+    }
+
+    /// Number of header rows of the table: the one stored in the table, or the detected one if
+    /// the table does not have one. The table is expected to be spanned.
+    pub fn n_header_rows(&self) -> usize {
+        self.header
+            .unwrap_or_else(|| TableHeader::detect(self))
+            .n_rows
+    }
+
+    /// Flatten the header rows of the table into one name per column, and return them with the
+    /// remaining body rows. The table is expected to be spanned.
+    ///
+    /// If `n_header_rows` is not given, [`Table::n_header_rows`] is used.
+    pub fn flatten_headers(&self, n_header_rows: Option<usize>) -> FlattenedTable {
+        FlattenedTable::new(self, n_header_rows.unwrap_or_else(|| self.n_header_rows()))
     }
 
     pub fn n_rows(&self) -> usize {
         self.rows.len()
     }
 
-    pub fn shape(&self, py: Python) -> (usize, usize) {
-        if self.rows.len() == 0 {
+    pub fn shape(&self) -> (usize, usize) {
+        if self.rows.is_empty() {
             (0, 0)
         } else {
-            (self.rows.len(), self.rows[0].borrow(py).cells.len())
+            (self.rows.len(), self.rows[0].cells.len())
         }
     }
 
-    pub fn get_cell(&self, py: Python, ri: usize, ci: usize) -> PyResult<Py<Cell>> {
-        if ri >= self.rows.len() {
-            return Err(PyKeyError::new_err(format!(
-                "Key {} is out of rows' range [0, {})",
-                ri,
-                self.rows.len()
-            )));
-        }
-        let row = self.rows[ri].borrow(py);
-        if ci >= row.cells.len() {
-            return Err(PyKeyError::new_err(format!(
-                "Key {} is out of cells' range [0, {})",
-                ci,
-                row.cells.len()
-            )));
-        }
+    pub fn get_row(&self, ri: usize) -> Option<&Row> {
+        self.rows.get(ri)
+    }
 
-        Ok(row.cells[ci].clone_ref(py))
+    pub fn get_cell(&self, ri: usize, ci: usize) -> Option<&Cell> {
+        self.rows.get(ri).and_then(|row| row.cells.get(ci))
     }
 
-    pub fn get_row(&self, py: Python, ri: usize) -> PyResult<Py<Row>> {
-        if ri >= self.rows.len() {
-            return Err(PyKeyError::new_err(format!(
-                "Key {} is out of rows' range [0, {})",
-                ri,
-                self.rows.len()
-            )));
-        }
-        Ok(self.rows[ri].clone_ref(py))
+    pub fn to_list(&self) -> Vec<Vec<String>> {
+        self.rows.iter().map(|r| r.to_list()).collect()
     }
 
-    pub fn iter_cells(slf: Py<Table>, py: Python) -> super::cell_iter::CellTIter {
-        super::cell_iter::CellTIter {
-            table: slf.clone_ref(py),
-            row_index: 0,
-            cell_index: 0,
-        }
+    /// Write the table as CSV (RFC 4180), rendering each cell with the given format.
+    ///
+    /// If `header` is false, the leading rows made of header cells are not written.
+    pub fn to_csv(&self, format: CellFormat, header: bool) -> String {
+        export::to_delimited(self, ',', format, header)
     }
 
-    pub fn enumerate_cells(slf: Py<Table>, py: Python) -> super::cell_iter::CellTEnumerator {
-        super::cell_iter::CellTEnumerator {
-            table: slf.clone_ref(py),
-            row_index: 0,
-            cell_index: 0,
-        }
+    /// Write the table as TSV, quoting fields the same way as [`Table::to_csv`].
+    pub fn to_tsv(&self, format: CellFormat, header: bool) -> String {
+        export::to_delimited(self, '\t', format, header)
     }
 
-    pub fn iter_rows(slf: Py<Table>, py: Python) -> super::row_iter::RowIter {
-        super::row_iter::RowIter {
-            table: slf.clone_ref(py),
-            row_index: 0,
-        }
+    /// Write the table as HTML. If `grid` is false, merged cells are written once with their
+    /// spans, otherwise every cell of the grid is written. See [`export::to_html`].
+    pub fn to_html(&self, grid: bool) -> String {
+        export::to_html(self, grid)
     }
 
-    pub fn to_bytes(&self) -> Result<Vec<u8>> {
-        let out = postcard::to_allocvec(self)?;
-        Ok(out)
+    /// Write the table as a GitHub-flavored markdown table. See [`export::to_markdown`].
+    pub fn to_markdown(&self, format: CellFormat, max_col_width: Option<usize>) -> String {
+        export::to_markdown(self, format, max_col_width)
     }
 
-    #[staticmethod]
-    pub fn from_bytes(bytes: &PyBytes) -> Result<Table> {
-        let table = postcard::from_bytes(bytes.as_bytes())?;
-        Ok(table)
+    /// Write the table as an aligned plain-text grid. See [`export::to_text_grid`].
+    pub fn to_text_grid(&self, format: CellFormat, max_col_width: Option<usize>) -> String {
+        export::to_text_grid(self, format, max_col_width)
+    }
+
+    /// Encode the table in the versioned binary format, see [`crate::models::format`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RSoupError> {
+        format::encode(DataKind::Table, self)
+    }
+
+    /// Decode a table from the versioned binary format, migrating tables of older versions
+    pub fn from_bytes(bytes: &[u8]) -> Result<Table, RSoupError> {
+        let (version, payload) = format::decode_header(DataKind::Table, bytes)?;
+        match version {
+            1 => Ok(format::decode_postcard::<TableV1>(DataKind::Table, version, payload)?.into()),
+            2 => Ok(format::decode_postcard::<TableV2>(DataKind::Table, version, payload)?.into()),
+            _ => format::decode_postcard(DataKind::Table, version, payload),
+        }
     }
 
     pub fn to_json(&self) -> Result<String> {
@@ -322,7 +513,6 @@ impl Table {
         Ok(out)
     }
 
-    #[staticmethod]
     pub fn from_json(dat: &str) -> Result<Table> {
         let out = serde_json::from_str(dat)?;
         Ok(out)
@@ -333,13 +523,50 @@ impl Table {
         Ok(out)
     }
 
-    #[staticmethod]
-    pub fn from_base64(b64s: &PyString) -> Result<Table> {
-        let bytes = base64::decode(b64s.to_str()?)?;
-        let table = postcard::from_bytes(&bytes)?;
-        Ok(table)
+    pub fn from_base64(b64s: &str) -> Result<Table> {
+        let bytes = base64::decode(b64s)?;
+        Ok(Table::from_bytes(&bytes)?)
     }
+}
 
+/// Put a copy of the downward-growing cells (rowspan = 0) in the given row of the grid
+fn grow_downward_growing_cells(grid_row: &mut Vec<Option<Cell>>, cells: &[(usize, usize, Cell)]) {
+    for (x, colspan, cell) in cells {
+        for xi in *x..(x + colspan) {
+            set_empty_slot(grid_row, xi, cell.clone());
+        }
+    }
+}
+
+/// Assign the cell to the slot of a row of the grid if the slot is not covered yet
+fn set_empty_slot(grid_row: &mut Vec<Option<Cell>>, x: usize, cell: Cell) {
+    if grid_row.len() <= x {
+        grid_row.resize(x + 1, None);
+    }
+    if grid_row[x].is_none() {
+        grid_row[x] = Some(cell);
+    }
+}
+
+/// Python wrapper of [`Table`]. Rows and context obtained from the table are views of the
+/// table, while its columns and the nested tables of its cells are returned as copies.
+///
+/// Operations on the table are delegated to [`Table`].
+#[cfg(feature = "python")]
+#[pyclass(module = "rsoup.core", name = "Table")]
+pub struct PyTable {
+    pub table: Table,
+}
+
+#[cfg(feature = "python")]
+impl From<Table> for PyTable {
+    fn from(table: Table) -> Self {
+        PyTable { table }
+    }
+}
+
+#[cfg(feature = "python")]
+impl Table {
     pub fn to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
         let o = PyDict::new(py);
 
@@ -352,7 +579,7 @@ impl Table {
             &self
                 .context
                 .iter()
-                .map(|c| c.borrow(py).to_dict(py))
+                .map(|c| c.to_dict(py))
                 .collect::<PyResult<Vec<_>>>()?,
         )?;
         o.set_item(
@@ -360,57 +587,378 @@ impl Table {
             &self
                 .rows
                 .iter()
-                .map(|r| r.borrow(py).to_dict(py))
+                .map(|r| r.to_dict(py))
                 .collect::<PyResult<Vec<_>>>()?,
         )?;
+        o.set_item("parent_id", &self.parent_id)?;
+        o.set_item(
+            "columns",
+            &self
+                .columns
+                .iter()
+                .map(|c| c.to_dict(py))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?;
+        match self.header {
+            None => o.set_item("header", py.None())?,
+            Some(header) => {
+                let d = PyDict::new(py);
+                d.set_item("n_rows", header.n_rows)?;
+                d.set_item("n_cols", header.n_cols)?;
+                o.set_item("header", d)?;
+            }
+        }
 
         Ok(o.into_py(py))
     }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyTable {
+    #[new]
+    #[args(
+        "*",
+        id = "String::new()",
+        url = "String::new()",
+        caption = "String::new()",
+        attrs = "HashMap::new()",
+        context = "Vec::new()",
+        rows = "Vec::new()",
+        parent_id = "None",
+        columns = "Vec::new()",
+        header = "None"
+    )]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        py: Python,
+        id: String,
+        url: String,
+        caption: String,
+        attrs: HashMap<String, String>,
+        context: Vec<PyRef<PyContentHierarchy>>,
+        rows: Vec<PyRef<PyRow>>,
+        parent_id: Option<String>,
+        columns: Vec<Column>,
+        header: Option<TableHeader>,
+    ) -> PyResult<Self> {
+        let mut table = Table::new(
+            id,
+            url,
+            caption,
+            attrs,
+            context
+                .iter()
+                .map(|c| c.with_content_hierarchy(py, ContentHierarchy::clone))
+                .collect::<PyResult<Vec<_>>>()?,
+            rows.iter()
+                .map(|r| r.with_row(py, Row::clone))
+                .collect::<PyResult<Vec<_>>>()?,
+        );
+        table.parent_id = parent_id;
+        table.columns = columns;
+        table.header = header;
+        Ok(PyTable::from(table))
+    }
 
-    pub fn to_list(&self, py: Python) -> PyResult<Vec<Vec<String>>> {
-        Ok(self.rows.iter().map(|r| r.borrow(py).to_list(py)).collect())
+    #[getter]
+    fn id(&self) -> &str {
+        &self.table.id
     }
 
-    fn __getstate__(&self, py: Python) -> PyResult<PyObject> {
-        Ok(PyBytes::new(py, &self.to_bytes()?).into())
+    #[setter]
+    fn set_id(&mut self, id: String) {
+        self.table.id = id;
     }
 
-    fn __setstate__(&mut self, py: Python, state: PyObject) -> PyResult<()> {
-        let b = state.as_ref(py).downcast::<PyBytes>()?;
-        let slf = Table::from_bytes(b)?;
+    #[getter]
+    fn url(&self) -> &str {
+        &self.table.url
+    }
 
-        self.id = slf.id;
-        self.url = slf.url;
-        self.caption = slf.caption;
-        self.attrs = slf.attrs;
-        self.context = slf.context;
-        self.rows = slf.rows;
+    #[setter]
+    fn set_url(&mut self, url: String) {
+        self.table.url = url;
+    }
 
-        Ok(())
+    #[getter]
+    fn caption(&self) -> &str {
+        &self.table.caption
     }
-}
 
-impl fmt::Debug for Table {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        Python::with_gil(|py| {
-            f.debug_struct("Table")
-                .field("id", &self.id)
-                .field("url", &self.url)
-                .field("caption", &self.caption)
-                .field("attrs", &self.attrs)
-                .field(
-                    "context",
-                    &self
-                        .context
-                        .iter()
-                        .map(|x| x.borrow(py))
-                        .collect::<Vec<_>>(),
-                )
-                .field(
-                    "rows",
-                    &self.rows.iter().map(|x| x.borrow(py)).collect::<Vec<_>>(),
-                )
-                .finish()
-        })
+    #[setter]
+    fn set_caption(&mut self, caption: String) {
+        self.table.caption = caption;
+    }
+
+    #[getter]
+    fn attrs(&self) -> HashMap<String, String> {
+        self.table.attrs.clone()
+    }
+
+    #[getter]
+    fn context(slf: PyRef<Self>, py: Python) -> Vec<PyContentHierarchy> {
+        let n_contexts = slf.table.context.len();
+        let slf: Py<Self> = slf.into();
+        (0..n_contexts)
+            .map(|i| PyContentHierarchy::in_table(slf.clone_ref(py), i))
+            .collect()
+    }
+
+    #[getter]
+    fn rows(slf: PyRef<Self>, py: Python) -> Vec<PyRow> {
+        let n_rows = slf.table.rows.len();
+        let slf: Py<Self> = slf.into();
+        (0..n_rows)
+            .map(|ri| PyRow::in_table(slf.clone_ref(py), ri))
+            .collect()
+    }
+
+    #[getter]
+    fn parent_id(&self) -> Option<&str> {
+        self.table.parent_id.as_deref()
+    }
+
+    #[setter]
+    fn set_parent_id(&mut self, parent_id: Option<String>) {
+        self.table.parent_id = parent_id;
+    }
+
+    #[getter]
+    fn columns(&self) -> Vec<Column> {
+        self.table.columns.clone()
+    }
+
+    #[getter]
+    fn header(&self) -> Option<TableHeader> {
+        self.table.header
+    }
+
+    #[setter]
+    fn set_header(&mut self, header: Option<TableHeader>) {
+        self.table.header = header;
+    }
+
+    /// Span the table by copying values to merged field
+    ///
+    /// `mode` is either "default" or "browser" (follow the HTML specification, never fails)
+    #[args(mode = "\"default\"")]
+    pub fn span(&self, mode: &str) -> PyResult<PyTable> {
+        let mode = mode.parse::<SpanMode>().map_err(PyValueError::new_err)?;
+        let table = self.table.span_with_mode(mode).map_err(into_pyerr)?;
+        Ok(PyTable::from(table))
+    }
+
+    /// Pad an irregular table (missing cells) to make it become a regular table
+    ///
+    /// This function only return new table when it's padded, otherwise, None.
+    pub fn pad(&self) -> Option<PyTable> {
+        self.table.pad().map(PyTable::from)
+    }
+
+    /// Detect the header rows and columns of the table and store them in the table.
+    /// The table is expected to be spanned. Numbers are parsed with the conventions of
+    /// the locale, see `parse_values`.
+    #[args(locale = "\"en\"")]
+    pub fn detect_headers(&mut self, locale: &str) -> PyResult<TableHeader> {
+        let parser = ValueParser::from_locale(locale).map_err(PyValueError::new_err)?;
+        let header = TableHeader::detect_with_parser(&self.table, &parser);
+        self.table.header = Some(header);
+        Ok(header)
+    }
+
+    /// Flatten the header rows of the table (expected to be spanned) into one name per column,
+    /// return the column names and the texts of the remaining body rows.
+    ///
+    /// Column names are the header texts joined with the separator, or the list of header
+    /// texts if the separator is None.
+    #[args(n_header_rows = "None", separator = "\" / \"")]
+    pub fn flatten_headers(
+        &self,
+        py: Python,
+        n_header_rows: Option<usize>,
+        separator: Option<&str>,
+    ) -> (PyObject, Vec<Vec<String>>) {
+        let flattened_table = self.table.flatten_headers(n_header_rows);
+        let columns = match separator {
+            Some(separator) => flattened_table.column_names(separator).into_py(py),
+            None => flattened_table.column_paths.clone().into_py(py),
+        };
+        (columns, flattened_table.to_list())
+    }
+
+    /// Write the table as CSV (RFC 4180). `format` is how cells are rendered: "text", "html"
+    /// or "markdown" (text rendered as markdown). If `header` is false, the leading rows made
+    /// of header cells are not written.
+    #[args(format = "\"text\"", header = "true")]
+    pub fn to_csv(&self, format: &str, header: bool) -> PyResult<String> {
+        let format = format
+            .parse::<CellFormat>()
+            .map_err(PyValueError::new_err)?;
+        Ok(self.table.to_csv(format, header))
+    }
+
+    /// Write the table as TSV, same as `to_csv`
+    #[args(format = "\"text\"", header = "true")]
+    pub fn to_tsv(&self, format: &str, header: bool) -> PyResult<String> {
+        let format = format
+            .parse::<CellFormat>()
+            .map_err(PyValueError::new_err)?;
+        Ok(self.table.to_tsv(format, header))
+    }
+
+    /// Write the table as HTML with thead/tbody/tfoot, caption, columns and attributes.
+    ///
+    /// If `grid` is false, merged cells are written once with rowspan/colspan, otherwise every
+    /// cell of the grid is written.
+    #[args(grid = "false")]
+    pub fn to_html(&self, grid: bool) -> String {
+        self.table.to_html(grid)
+    }
+
+    /// Write the table as a GitHub-flavored markdown table, `|` and line breaks in cells are
+    /// escaped. `format` is how cells are rendered, e.g., "markdown" to keep links and emphasis, and
+    /// cells wider than `max_col_width` are truncated.
+    #[args(format = "\"text\"", max_col_width = "None")]
+    pub fn to_markdown(&self, format: &str, max_col_width: Option<usize>) -> PyResult<String> {
+        let format = format
+            .parse::<CellFormat>()
+            .map_err(PyValueError::new_err)?;
+        Ok(self.table.to_markdown(format, max_col_width))
+    }
+
+    /// Write the table as an aligned plain-text grid, same options as `to_markdown`
+    #[args(format = "\"text\"", max_col_width = "None")]
+    pub fn to_text_grid(&self, format: &str, max_col_width: Option<usize>) -> PyResult<String> {
+        let format = format
+            .parse::<CellFormat>()
+            .map_err(PyValueError::new_err)?;
+        Ok(self.table.to_text_grid(format, max_col_width))
+    }
+
+    /// Parse the text of every cell into a typed value, with the decimal and thousands
+    /// separators of the locale, e.g., "en", "de" or "fr"
+    #[args(locale = "\"en\"")]
+    pub fn parse_values(&self, locale: &str) -> PyResult<Vec<Vec<TypedValue>>> {
+        let parser = ValueParser::from_locale(locale).map_err(PyValueError::new_err)?;
+        Ok(self.table.parse_values(&parser))
+    }
+
+    /// Infer the dominant type of each column from the cells below the header rows
+    #[args(locale = "\"en\"")]
+    pub fn column_types(&self, locale: &str) -> PyResult<Vec<&'static str>> {
+        let parser = ValueParser::from_locale(locale).map_err(PyValueError::new_err)?;
+        Ok(self
+            .table
+            .column_types(&parser)
+            .iter()
+            .map(|t| t.as_str())
+            .collect())
+    }
+
+    pub fn n_rows(&self) -> usize {
+        self.table.rows.len()
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        if self.table.rows.is_empty() {
+            (0, 0)
+        } else {
+            (self.table.rows.len(), self.table.rows[0].cells.len())
+        }
+    }
+
+    pub fn get_cell(slf: PyRef<Self>, py: Python, ri: usize, ci: usize) -> PyResult<PyCell> {
+        let row = Py::new(py, PyTable::get_row(slf, ri)?)?;
+        let n_cells = row.borrow(py).with_row(py, |row| row.cells.len())?;
+        if ci >= n_cells {
+            return Err(PyKeyError::new_err(format!(
+                "Key {} is out of cells' range [0, {})",
+                ci, n_cells
+            )));
+        }
+        Ok(PyCell::in_row(row, ci))
+    }
+
+    pub fn get_row(slf: PyRef<Self>, ri: usize) -> PyResult<PyRow> {
+        let n_rows = slf.table.rows.len();
+        if ri >= n_rows {
+            return Err(PyKeyError::new_err(format!(
+                "Key {} is out of rows' range [0, {})",
+                ri, n_rows
+            )));
+        }
+        Ok(PyRow::in_table(slf.into(), ri))
+    }
+
+    pub fn iter_cells(slf: Py<PyTable>, py: Python) -> super::cell_iter::CellTIter {
+        super::cell_iter::CellTIter {
+            table: slf.clone_ref(py),
+            row_index: 0,
+            cell_index: 0,
+        }
+    }
+
+    pub fn enumerate_cells(slf: Py<PyTable>, py: Python) -> super::cell_iter::CellTEnumerator {
+        super::cell_iter::CellTEnumerator {
+            table: slf.clone_ref(py),
+            row_index: 0,
+            cell_index: 0,
+        }
+    }
+
+    pub fn iter_rows(slf: Py<PyTable>, py: Python) -> super::row_iter::RowIter {
+        super::row_iter::RowIter {
+            table: slf.clone_ref(py),
+            row_index: 0,
+        }
+    }
+
+    pub fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let out = self.table.to_bytes().map_err(into_pyerr)?;
+        Ok(PyBytes::new(py, &out))
+    }
+
+    #[staticmethod]
+    pub fn from_bytes(bytes: &PyBytes) -> PyResult<PyTable> {
+        Ok(PyTable::from(
+            Table::from_bytes(bytes.as_bytes()).map_err(into_pyerr)?,
+        ))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        self.table.to_json()
+    }
+
+    #[staticmethod]
+    pub fn from_json(dat: &str) -> Result<PyTable> {
+        Ok(PyTable::from(Table::from_json(dat)?))
+    }
+
+    pub fn to_base64(&self) -> Result<String> {
+        self.table.to_base64()
+    }
+
+    #[staticmethod]
+    pub fn from_base64(b64s: &PyString) -> PyResult<PyTable> {
+        Ok(PyTable::from(Table::from_base64(b64s.to_str()?)?))
+    }
+
+    #[pyo3(name = "to_dict")]
+    pub fn py_to_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
+        self.table.to_dict(py)
+    }
+
+    pub fn to_list(&self) -> Vec<Vec<String>> {
+        self.table.to_list()
+    }
+
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        self.to_bytes(py)
+    }
+
+    fn __setstate__(&mut self, state: &PyBytes) -> PyResult<()> {
+        self.table = Table::from_bytes(state.as_bytes()).map_err(into_pyerr)?;
+        Ok(())
     }
 }
@@ -0,0 +1,509 @@
+//! Typed values of cells: numbers, percentages, quantities with a currency or a known unit
+//! (see [`UNITS`]), dates and text, parsed from the text of the cells.
+//!
+//! Before parsing, footnote markers (e.g., `[a]`, `[12]`, `[note 1]` or trailing `*`, `†`)
+//! are removed, whitespaces are collapsed, and Unicode minus signs and dashes in front of
+//! numbers are read as `-`. Decimal and thousands separators and the order of day and month
+//! in numeric dates depend on the locale of the parser. Month names are in English.
+
+use std::str::FromStr;
+
+use phf::{phf_set, Set};
+use regex::Regex;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use super::{Cell, Table, TableHeader};
+
+lazy_static! {
+    static ref RE_FOOTNOTE: Regex = Regex::new(r"\[([\p{L}\p{N} .\-]{1,24})\]").unwrap();
+    static ref RE_TRAILING_FOOTNOTE: Regex = Regex::new(r"[*†‡§¶]+$").unwrap();
+    static ref RE_ISO_DATE: Regex = Regex::new(r"^(\d{4})-(\d{1,2})(?:-(\d{1,2}))?$").unwrap();
+    static ref RE_DAY_MONTH_YEAR: Regex =
+        Regex::new(r"^(\d{1,2})(?:st|nd|rd|th)?\s+(\p{L}+)\.?,?\s+(\d{4})$").unwrap();
+    static ref RE_MONTH_DAY_YEAR: Regex =
+        Regex::new(r"^(\p{L}+)\.?\s+(\d{1,2})(?:st|nd|rd|th)?,?\s+(\d{4})$").unwrap();
+    static ref RE_MONTH_YEAR: Regex = Regex::new(r"^(\p{L}+)\.?,?\s+(\d{4})$").unwrap();
+    static ref RE_NUMERIC_DATE: Regex =
+        Regex::new(r"^(\d{1,2})[/.](\d{1,2})[/.](\d{4})$").unwrap();
+    static ref RE_CURRENCY_PREFIX: Regex =
+        Regex::new(r"^(?P<unit>[A-Z]{0,3}\p{Sc})\s?(?P<num>[-+]?[0-9.,'’ ]+)$").unwrap();
+    // a number followed by a unit, and optionally by a conversion in parentheses,
+    // e.g., "8,848 m (29,029 ft)". the unit must be a currency or one of UNITS
+    static ref RE_QUANTITY: Regex = Regex::new(
+        r"^(?P<num>[-+]?[0-9][0-9.,'’ ]*?)\s*(?P<unit>[\p{L}\p{Sc}\p{So}][^\d()\[\]]{0,19}?)\s*(?:\(.*\))?$"
+    )
+    .unwrap();
+    static ref RE_CURRENCY_UNIT: Regex = Regex::new(r"^[A-Z]{0,3}\p{Sc}$").unwrap();
+}
+
+/// Units of quantities written after numbers, other than currencies. Any word cannot be a
+/// unit as texts such as "2016 Summer Olympics" or "3 wins" are not quantities.
+pub static UNITS: Set<&'static str> = phf_set! {
+    // length and area
+    "mm", "cm", "m", "km", "in", "ft", "yd", "mi", "nmi",
+    "mm²", "cm²", "m²", "km²", "ft²", "mi²", "m2", "km2", "sq m", "sq km", "sq ft", "sq mi",
+    "ha", "acre", "acres",
+    // volume
+    "ml", "mL", "cl", "l", "L", "m³", "ft³", "gal", "cu ft",
+    // mass
+    "mg", "g", "kg", "t", "lb", "lbs", "oz", "st",
+    // time
+    "ms", "s", "min", "h", "hr", "hrs",
+    "second", "seconds", "minute", "minutes", "hour", "hours", "day", "days",
+    "week", "weeks", "month", "months", "year", "years",
+    // speed
+    "m/s", "km/h", "mph", "kn", "knots",
+    // temperature and angle
+    "°", "°C", "°F", "℃", "℉", "K",
+    // energy, power and electricity
+    "J", "kJ", "MJ", "cal", "kcal", "Wh", "kWh", "MWh", "GWh", "TWh",
+    "W", "kW", "MW", "GW", "hp", "V", "kV", "A", "mA", "Hz", "kHz", "MHz", "GHz",
+    // pressure
+    "Pa", "hPa", "kPa", "bar", "mbar", "psi", "atm",
+    // data
+    "B", "kB", "KB", "MB", "GB", "TB", "bit", "kbit/s", "Mbit/s", "Gbit/s",
+    // currency codes
+    "USD", "EUR", "GBP", "JPY", "CNY", "CHF", "CAD", "AUD", "INR", "RUB",
+};
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Type of the value of a cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Empty,
+    Number,
+    Percentage,
+    Quantity,
+    Date,
+    Text,
+}
+
+impl ValueType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValueType::Empty => "empty",
+            ValueType::Number => "number",
+            ValueType::Percentage => "percentage",
+            ValueType::Quantity => "quantity",
+            ValueType::Date => "date",
+            ValueType::Text => "text",
+        }
+    }
+
+    /// Most frequent type of non-empty values, ties are broken in the order: number,
+    /// percentage, quantity, date, text. Returns `Empty` if all values are empty.
+    pub fn dominant<I: IntoIterator<Item = ValueType>>(types: I) -> ValueType {
+        let candidates = [
+            ValueType::Number,
+            ValueType::Percentage,
+            ValueType::Quantity,
+            ValueType::Date,
+            ValueType::Text,
+        ];
+        let mut counts = [0usize; 5];
+        for value_type in types {
+            if let Some(i) = candidates.iter().position(|t| *t == value_type) {
+                counts[i] += 1;
+            }
+        }
+
+        let mut dominant = ValueType::Empty;
+        let mut max_count = 0;
+        for (value_type, count) in candidates.into_iter().zip(counts) {
+            if count > max_count {
+                dominant = value_type;
+                max_count = count;
+            }
+        }
+        dominant
+    }
+}
+
+impl FromStr for ValueType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "empty" => Ok(ValueType::Empty),
+            "number" => Ok(ValueType::Number),
+            "percentage" => Ok(ValueType::Percentage),
+            "quantity" => Ok(ValueType::Quantity),
+            "date" => Ok(ValueType::Date),
+            "text" => Ok(ValueType::Text),
+            _ => Err(format!("Invalid value type: {}", s)),
+        }
+    }
+}
+
+/// A date, possibly without its day (e.g., "March 2016")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: Option<u8>,
+}
+
+impl Date {
+    /// Create a date, returns None if the month or the day does not exist
+    pub fn new(year: i32, month: u8, day: Option<u8>) -> Option<Date> {
+        let n_days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => return None,
+        };
+        match day {
+            Some(day) if day == 0 || day > n_days => None,
+            _ => Some(Date { year, month, day }),
+        }
+    }
+}
+
+/// Value parsed from the text of a cell
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "python", pyclass(module = "rsoup.core"))]
+pub struct TypedValue {
+    pub value_type: ValueType,
+    // value of numbers, percentages (12 for "12 %") and quantities
+    pub number: Option<f64>,
+    // unit of quantities (e.g., "m", "$") and "%" for percentages
+    pub unit: Option<String>,
+    pub date: Option<Date>,
+    // text of the cell without the footnote markers and with collapsed whitespaces
+    pub text: String,
+    // content of the removed footnote markers, e.g., "a" for "[a]"
+    pub footnotes: Vec<String>,
+}
+
+/// Parse the text of cells with the number and date conventions of a locale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueParser {
+    pub decimal_separator: char,
+    pub thousands_separators: Vec<char>,
+    // whether numeric dates are written day first (31/12/2016) or month first (12/31/2016)
+    pub day_first: bool,
+}
+
+impl Default for ValueParser {
+    fn default() -> Self {
+        ValueParser::new('.', vec![','], false)
+    }
+}
+
+impl ValueParser {
+    pub fn new(decimal_separator: char, thousands_separators: Vec<char>, day_first: bool) -> Self {
+        ValueParser {
+            decimal_separator,
+            thousands_separators,
+            day_first,
+        }
+    }
+
+    /// Create a parser for a locale given as a language code with an optional region,
+    /// e.g., "en", "en-GB", "de", "fr_FR", "de-CH".
+    pub fn from_locale(locale: &str) -> Result<ValueParser, String> {
+        let locale = locale.to_ascii_lowercase().replace('_', "-");
+        let (language, region) = match locale.split_once('-') {
+            Some((language, region)) => (language, Some(region)),
+            None => (locale.as_str(), None),
+        };
+
+        let parser = match (language, region) {
+            ("en", None | Some("us")) => ValueParser::new('.', vec![','], false),
+            ("en", _) => ValueParser::new('.', vec![','], true),
+            ("ja" | "zh" | "ko", _) => ValueParser::new('.', vec![','], false),
+            ("th" | "he" | "ga", _) => ValueParser::new('.', vec![','], true),
+            ("de" | "it" | "fr", Some("ch" | "li")) => ValueParser::new('.', vec!['\'', '’'], true),
+            ("de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro", _) => {
+                ValueParser::new(',', vec!['.', ' '], true)
+            }
+            (
+                "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg",
+                _,
+            ) => ValueParser::new(',', vec![' '], true),
+            _ => return Err(format!("Unsupported locale: {}", locale)),
+        };
+        Ok(parser)
+    }
+
+    pub fn parse(&self, text: &str) -> TypedValue {
+        let mut footnotes = Vec::new();
+        let text = RE_FOOTNOTE.replace_all(text, |caps: &regex::Captures| {
+            footnotes.push(caps[1].trim().to_owned());
+            " "
+        });
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let text = match RE_TRAILING_FOOTNOTE.find(&text) {
+            Some(m) if m.start() > 0 => {
+                footnotes.push(m.as_str().to_owned());
+                text[..m.start()].trim_end().to_owned()
+            }
+            _ => text,
+        };
+
+        let mut value = TypedValue {
+            value_type: ValueType::Text,
+            number: None,
+            unit: None,
+            date: None,
+            text,
+            footnotes,
+        };
+        let normalized = normalize_minus(&value.text);
+        let s = normalized.as_str();
+
+        if s.is_empty() {
+            value.value_type = ValueType::Empty;
+        } else if let Some(number) = self.parse_number(s) {
+            value.value_type = ValueType::Number;
+            value.number = Some(number);
+        } else if let Some(number) = s
+            .strip_suffix(['%', '％'])
+            .and_then(|s| self.parse_number(s.trim_end()))
+        {
+            value.value_type = ValueType::Percentage;
+            value.number = Some(number);
+            value.unit = Some("%".to_owned());
+        } else if let Some(date) = self.parse_date(s) {
+            value.value_type = ValueType::Date;
+            value.date = Some(date);
+        } else if let Some((number, unit)) = self.parse_quantity(s) {
+            value.value_type = ValueType::Quantity;
+            value.number = Some(number);
+            value.unit = Some(unit);
+        }
+        value
+    }
+
+    /// Parse a number with an optional sign, thousands separators (between groups of three
+    /// digits) and decimal separator
+    pub fn parse_number(&self, s: &str) -> Option<f64> {
+        let (sign, body) = match s.strip_prefix('-') {
+            Some(body) => ("-", body),
+            None => ("", s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int_part, frac_part) = match body.split_once(self.decimal_separator) {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (body, None),
+        };
+        if let Some(frac_part) = frac_part {
+            if frac_part.is_empty() || !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+        }
+
+        let int_digits = match int_part
+            .chars()
+            .find(|c| self.thousands_separators.contains(c))
+        {
+            Some(sep) => {
+                let groups = int_part.split(sep).collect::<Vec<_>>();
+                let is_valid = groups.iter().enumerate().all(|(i, group)| {
+                    let n = group.len();
+                    (if i == 0 { (1..=3).contains(&n) } else { n == 3 })
+                        && group.bytes().all(|b| b.is_ascii_digit())
+                });
+                if !is_valid {
+                    return None;
+                }
+                groups.concat()
+            }
+            None if int_part.is_empty() && frac_part.is_some() => "0".to_owned(),
+            None if !int_part.is_empty() && int_part.bytes().all(|b| b.is_ascii_digit()) => {
+                int_part.to_owned()
+            }
+            None => return None,
+        };
+
+        format!("{}{}.{}", sign, int_digits, frac_part.unwrap_or("0"))
+            .parse()
+            .ok()
+    }
+
+    /// Parse a date such as "3 March 2016", "March 3, 2016", "March 2016", "2016-03-03" or
+    /// "03/03/2016"
+    pub fn parse_date(&self, s: &str) -> Option<Date> {
+        if let Some(caps) = RE_ISO_DATE.captures(s) {
+            return Date::new(
+                caps[1].parse().ok()?,
+                caps[2].parse().ok()?,
+                caps.get(3).and_then(|m| m.as_str().parse().ok()),
+            );
+        }
+        if let Some(caps) = RE_DAY_MONTH_YEAR.captures(s) {
+            return Date::new(
+                caps[3].parse().ok()?,
+                parse_month(&caps[2])?,
+                Some(caps[1].parse().ok()?),
+            );
+        }
+        if let Some(caps) = RE_MONTH_DAY_YEAR.captures(s) {
+            return Date::new(
+                caps[3].parse().ok()?,
+                parse_month(&caps[1])?,
+                Some(caps[2].parse().ok()?),
+            );
+        }
+        if let Some(caps) = RE_MONTH_YEAR.captures(s) {
+            return Date::new(caps[2].parse().ok()?, parse_month(&caps[1])?, None);
+        }
+        if let Some(caps) = RE_NUMERIC_DATE.captures(s) {
+            let (day, month) = if self.day_first {
+                (&caps[1], &caps[2])
+            } else {
+                (&caps[2], &caps[1])
+            };
+            return Date::new(
+                caps[3].parse().ok()?,
+                month.parse().ok()?,
+                Some(day.parse().ok()?),
+            );
+        }
+        None
+    }
+
+    /// Parse a number with a currency symbol before it, or a currency or a unit of [`UNITS`]
+    /// after it
+    pub fn parse_quantity(&self, s: &str) -> Option<(f64, String)> {
+        let caps = RE_CURRENCY_PREFIX.captures(s).or_else(|| {
+            RE_QUANTITY.captures(s).filter(|caps| {
+                let unit = caps["unit"].trim_end();
+                UNITS.contains(unit) || RE_CURRENCY_UNIT.is_match(unit)
+            })
+        })?;
+        let number = self.parse_number(caps["num"].trim_end())?;
+        Some((number, caps["unit"].trim_end().to_owned()))
+    }
+}
+
+/// Read Unicode minus signs and dashes in front of a digit as `-`
+fn normalize_minus(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let is_minus = matches!(
+            c,
+            '\u{2212}' | '\u{2012}' | '\u{2013}' | '\u{FE63}' | '\u{FF0D}'
+        ) && out.is_empty()
+            && chars.peek().is_some_and(|next| next.is_ascii_digit());
+        out.push(if is_minus { '-' } else { c });
+    }
+    out
+}
+
+/// Month of an English month name or of its three-letter abbreviation (or "sept")
+fn parse_month(name: &str) -> Option<u8> {
+    let name = name.to_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| {
+            *month == name
+                || (name.len() == 3 && month.starts_with(&name))
+                || (name == "sept" && *month == "september")
+        })
+        .map(|i| i as u8 + 1)
+}
+
+impl Cell {
+    /// Parse the text of the cell into a typed value
+    pub fn parse_value(&self, parser: &ValueParser) -> TypedValue {
+        parser.parse(&self.value.text)
+    }
+}
+
+impl Table {
+    /// Parse the text of every cell into a typed value, in the order of the rows and cells
+    pub fn parse_values(&self, parser: &ValueParser) -> Vec<Vec<TypedValue>> {
+        self.rows
+            .iter()
+            .map(|row| row.cells.iter().map(|c| c.parse_value(parser)).collect())
+            .collect()
+    }
+
+    /// Infer the dominant type of each column (see [`ValueType::dominant`]) from the cells
+    /// below the header rows: the ones stored in the table, or the ones detected with the
+    /// parser. The table is expected to be spanned.
+    pub fn column_types(&self, parser: &ValueParser) -> Vec<ValueType> {
+        let n_header_rows = self
+            .header
+            .unwrap_or_else(|| TableHeader::detect_with_parser(self, parser))
+            .n_rows;
+        let n_cols = self.rows.iter().map(|r| r.cells.len()).max().unwrap_or(0);
+
+        (0..n_cols)
+            .map(|ci| {
+                ValueType::dominant(
+                    self.rows
+                        .iter()
+                        .skip(n_header_rows)
+                        .filter_map(|row| row.cells.get(ci))
+                        .map(|cell| cell.parse_value(parser).value_type),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl TypedValue {
+    /// One of "empty", "number", "percentage", "quantity", "date" or "text"
+    #[getter]
+    fn value_type(&self) -> &'static str {
+        self.value_type.as_str()
+    }
+
+    #[getter]
+    fn number(&self) -> Option<f64> {
+        self.number
+    }
+
+    #[getter]
+    fn unit(&self) -> Option<String> {
+        self.unit.clone()
+    }
+
+    /// Year, month and day (None if the date has no day) of dates
+    #[getter]
+    fn date(&self) -> Option<(i32, u8, Option<u8>)> {
+        self.date.map(|d| (d.year, d.month, d.day))
+    }
+
+    #[getter]
+    fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    #[getter]
+    fn footnotes(&self) -> Vec<String> {
+        self.footnotes.clone()
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "TypedValue(value_type={}, number={}, unit={}, date={}, text={})",
+            self.value_type().into_py(py).as_ref(py).repr()?,
+            self.number.into_py(py).as_ref(py).repr()?,
+            self.unit.clone().into_py(py).as_ref(py).repr()?,
+            self.date().into_py(py).as_ref(py).repr()?,
+            self.text.clone().into_py(py).as_ref(py).repr()?,
+        ))
+    }
+}
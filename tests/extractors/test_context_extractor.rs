@@ -1,6 +1,5 @@
 use crate::get_doc;
 use anyhow::Result;
-use pyo3::Python;
 use rsoup::extractors::context_v1::ContextExtractor;
 use scraper::{Node, Selector};
 
@@ -17,7 +16,7 @@ fn test_locate_content_before_and_after() -> Result<()> {
     let (tree_before, tree_after) = extractor.locate_content_before_and_after(*elements[0])?;
 
     let node_key = |uid| match tree_before.get_node(uid).value() {
-        Node::Element(x) => format!("{}", x.name()),
+        Node::Element(x) => x.name().to_string(),
         Node::Text(x) => format!("`{}`", &x[..x.len().min(20)].replace("\n", "\\n")),
         _ => format!("{}", uid),
     };
@@ -62,7 +61,7 @@ fn test_flatten_node() -> Result<()> {
     assert_eq!(elements.len(), 1);
 
     let mut output = Vec::new();
-    extractor.flatten_node(&*elements[0], &mut output);
+    extractor.flatten_node(&elements[0], &mut output);
 
     // println!("{:#?}", output);
     assert_eq!(
@@ -80,7 +79,7 @@ fn test_flatten_node() -> Result<()> {
     );
 
     let mut output_recur = Vec::new();
-    extractor.flatten_node_recur(&*elements[0], &mut output_recur);
+    extractor.flatten_node_recur(&elements[0], &mut output_recur);
     assert_eq!(output, output_recur);
     Ok(())
 }
@@ -94,9 +93,7 @@ fn test_context_extractor() -> Result<()> {
 
     let elements = doc.html.select(&selector).collect::<Vec<_>>();
     assert_eq!(elements.len(), 1);
-    let gil = Python::acquire_gil();
-    let py = gil.python();
-    let context = extractor.extract_context(py, *elements[0])?;
+    let context = extractor.extract_context(*elements[0])?;
 
     // println!("{:#?}", context);
     assert_eq!(
@@ -3,7 +3,6 @@ from typing import List
 
 from tests.conftest import Webpage
 from rsoup.core import TableExtractor, ContextExtractor
-from tabulate import tabulate
 
 
 @pytest.fixture
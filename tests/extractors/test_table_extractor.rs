@@ -1,48 +1,247 @@
-use crate::get_doc;
+use crate::{extract_tables, get_doc, get_tables, table_extractor};
 use anyhow::Result;
-use pyo3::Python;
 use rsoup::{
-    extractors::{context_v1::ContextExtractor, table::TableExtractor, Document},
-    models::table::Table,
+    extractors::Document,
+    models::{
+        extraction_report::SkipReason,
+        table::{RowSection, SpanMode, Table},
+    },
 };
 use scraper::Selector;
 
-fn get_tables(filename: &str, testcase: Option<&str>) -> Result<Vec<Table>> {
-    let gil = Python::acquire_gil();
-    let py = gil.python();
+/// Extract the tables of the element of a test resource whose id is `testcase`
+fn get_testcase_tables(filename: &str, testcase: &str) -> Result<Vec<Table>> {
+    let doc = get_doc(filename)?;
+    let selector = Selector::parse(&format!("#{}", testcase)).unwrap();
+    let el = doc.html.select(&selector).next().unwrap();
+    let doc = Document::new(doc.url, el.html());
 
-    let extractor = TableExtractor::new(ContextExtractor::default(), None, None, None, true, false);
-    let mut doc = get_doc(filename)?;
-
-    if testcase.is_some() {
-        let selector = Selector::parse(&format!("#{}", testcase.unwrap())).unwrap();
-        let el = doc.html.select(&selector).nth(0).unwrap();
-        doc = Document::new(doc.url, el.html());
-    }
-
-    Ok(extractor.extract_tables(py, &doc, false, false, false)?)
+    table_extractor(SpanMode::Default, false, false).extract_tables(&doc, false, false, false)
 }
 
 #[test]
 fn test_extract_empty_table() -> Result<()> {
-    let gil = Python::acquire_gil();
-    let py = gil.python();
-
-    let tables = get_tables(
+    let tables = get_testcase_tables(
         "extractors/table.html",
-        Some("infobox-with-nested-opt-empty-tables"),
+        "infobox-with-nested-opt-empty-tables",
     )?;
     assert_eq!(tables.len(), 1);
     assert_eq!(
-        tables[0].to_list(py)?,
+        tables[0].to_list(),
         vec![vec!["← 2012", "October 15, 2016", "2020 →",]]
     );
 
-    let tables = get_tables("wikipedia/2016_Nova_Scotia_municipal_elections.html", None)?;
+    let tables = get_tables(
+        "wikipedia/2016_Nova_Scotia_municipal_elections.html",
+        false,
+        false,
+        false,
+    )?;
     assert_eq!(
-        tables[1].to_list(py)?[0],
+        tables[1].to_list()[0],
         vec!["Mayoral candidate[1]", "Vote", "%",]
     );
 
     Ok(())
 }
+
+#[test]
+fn test_extraction_report() -> Result<()> {
+    let html = r#"<table>
+            <tr><td>a</td><td><table><tr><td>nested</td></tr></table></td></tr>
+        </table>
+        <table><tr></tr></table>
+        <table>
+            <tr><td>a</td><td rowspan="2">b</td></tr>
+            <tr><td colspan="2">c</td><td>d</td></tr>
+        </table>
+        <table><tr><td>e</td></tr></table>"#;
+
+    let doc = Document::new("https://example.org/page".to_owned(), html.to_owned());
+    let (tables, report) = table_extractor(SpanMode::Default, false, false)
+        .extract_tables_with_report(&doc, true, false, false)?;
+    assert_eq!(tables.len(), 2);
+    assert_eq!(tables[0].to_list(), vec![vec!["nested"]]);
+    assert_eq!(tables[1].to_list(), vec![vec!["e"]]);
+
+    assert_eq!(report.tables.len(), 5);
+    assert_eq!(report.n_extracted(), 2);
+    assert_eq!(report.n_skipped(), 3);
+    assert_eq!(
+        report
+            .tables
+            .iter()
+            .map(|t| (t.index, t.skip_reason))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, Some(SkipReason::NestedTable)),
+            (1, None),
+            (2, Some(SkipReason::NoCells)),
+            (3, Some(SkipReason::SpanError)),
+            (4, None),
+        ]
+    );
+    assert_eq!(
+        report.tables[3].table_id.as_deref(),
+        Some("https://example.org/page?table_no=1")
+    );
+    assert_eq!(report.tables[3].errors.len(), 1);
+    assert_eq!(
+        report.tables[4].table_id.as_deref(),
+        Some(tables[1].id.as_str())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_nested_tables() -> Result<()> {
+    let html = r#"<table>
+            <tr><th>name</th><th>detail</th></tr>
+            <tr>
+                <td>a</td>
+                <td>
+                    <table><tr><td>x</td><td>1</td></tr></table>
+                    <div><table><tr><td>y</td><td><table><tr><td>z</td></tr></table></td></tr></table></div>
+                </td>
+            </tr>
+        </table>
+        <table><tr><td>b</td></tr></table>"#;
+
+    let doc = Document::new("https://example.org/page".to_owned(), html.to_owned());
+    let (tables, report) = table_extractor(SpanMode::Default, false, true)
+        .extract_tables_with_report(&doc, true, true, false)?;
+    assert_eq!(tables.len(), 2);
+    assert_eq!(
+        tables[0].to_list(),
+        vec![vec!["name", "detail"], vec!["a", ""]]
+    );
+    assert_eq!(tables[0].id, "https://example.org/page?table_no=0");
+    assert_eq!(tables[0].parent_id, None);
+    assert_eq!(tables[1].id, "https://example.org/page?table_no=4");
+
+    let cell = tables[0].get_cell(1, 1).unwrap();
+    assert_eq!(cell.nested_tables.len(), 2);
+    assert_eq!(cell.nested_tables[0].to_list(), vec![vec!["x", "1"]]);
+    assert_eq!(
+        cell.nested_tables[0].parent_id.as_deref(),
+        Some(tables[0].id.as_str())
+    );
+
+    let inner = &cell.nested_tables[1];
+    assert_eq!(inner.id, "https://example.org/page?table_no=2");
+    let innermost = &inner.get_cell(0, 1).unwrap().nested_tables[0];
+    assert_eq!(innermost.to_list(), vec![vec!["z"]]);
+    assert_eq!(innermost.parent_id.as_deref(), Some(inner.id.as_str()));
+
+    assert_eq!(report.tables.len(), 5);
+    assert_eq!(report.n_extracted(), 5);
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_nested_tables_in_merged_cell() -> Result<()> {
+    let html = r#"<table>
+            <tr><td colspan="2" rowspan="2"><table><tr><td>x</td></tr></table></td><td>a</td></tr>
+            <tr><td>b</td></tr>
+            <tr><td>c</td><td>d</td><td>e</td></tr>
+        </table>"#;
+
+    let doc = Document::new("https://example.org/page".to_owned(), html.to_owned());
+    for span_mode in [SpanMode::Default, SpanMode::Browser] {
+        let (tables, report) = table_extractor(span_mode, false, true)
+            .extract_tables_with_report(&doc, true, true, false)?;
+        assert_eq!(tables[0].shape(), (3, 3));
+        // the nested table stays in the top-left cell, the span copies do not duplicate it
+        let n_nested_tables = tables[0]
+            .rows
+            .iter()
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| cell.nested_tables.len())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            n_nested_tables,
+            vec![vec![1, 0, 0], vec![0, 0, 0], vec![0, 0, 0]]
+        );
+        assert_eq!(report.tables.len(), 2);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_row_sections() -> Result<()> {
+    let html = r#"<table>
+            <caption>scores</caption>
+            <tfoot><tr><td>total</td><td>3</td></tr></tfoot>
+            <tbody><tr><td>a</td><td>1</td></tr></tbody>
+            <thead><tr><th>name</th><th>score</th></tr></thead>
+            <tbody><tr><td>b</td><td>2</td></tr></tbody>
+        </table>"#;
+
+    let tables = extract_tables(html, None)?;
+    assert_eq!(tables[0].caption, "scores");
+    assert_eq!(
+        tables[0].to_list(),
+        vec![
+            vec!["name", "score"],
+            vec!["a", "1"],
+            vec!["b", "2"],
+            vec!["total", "3"],
+        ]
+    );
+    assert_eq!(
+        tables[0].rows.iter().map(|r| r.section).collect::<Vec<_>>(),
+        vec![
+            RowSection::THead,
+            RowSection::TBody,
+            RowSection::TBody,
+            RowSection::TFoot
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_columns() -> Result<()> {
+    let html = r#"<table>
+            <colgroup><col class="name"><col span="2" class="score"></colgroup>
+            <colgroup span="2" class="extra"></colgroup>
+            <tr><th>name</th><th colspan="2">score</th><th colspan="2">extra</th></tr>
+            <tr><td>a</td><td>1</td><td>2</td><td>3</td><td>4</td></tr>
+        </table>"#;
+
+    let tables = extract_tables(html, None)?;
+    let columns = &tables[0].columns;
+    assert_eq!(
+        columns
+            .iter()
+            .map(|c| (c.span, c.attrs["class"].as_str()))
+            .collect::<Vec<_>>(),
+        vec![(1, "name"), (2, "score"), (2, "extra")]
+    );
+
+    let table = tables[0].span()?;
+    assert_eq!(
+        table
+            .columns
+            .iter()
+            .map(|c| (c.span, c.attrs["class"].as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (1, "name"),
+            (1, "score"),
+            (1, "score"),
+            (1, "extra"),
+            (1, "extra")
+        ]
+    );
+
+    Ok(())
+}
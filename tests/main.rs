@@ -1,5 +1,8 @@
 use anyhow::Result;
-use rsoup::extractors::Document;
+use rsoup::{
+    extractors::{context_v1::ContextExtractor, table::TableExtractor, Document},
+    models::table::{SpanMode, Table},
+};
 use std::{fs, path::Path};
 
 #[cfg(test)]
@@ -19,3 +22,50 @@ pub fn get_doc(filename: &str) -> Result<Document> {
 
     Ok(Document::new(url, html))
 }
+
+/// Table extractor used by the tests, with the default tags
+pub fn table_extractor(
+    span_mode: SpanMode,
+    html_error_forgiveness: bool,
+    extract_nested_tables: bool,
+) -> TableExtractor {
+    TableExtractor::new(
+        ContextExtractor::default(),
+        None,
+        None,
+        None,
+        true,
+        html_error_forgiveness,
+        extract_nested_tables,
+        span_mode,
+    )
+}
+
+/// Extract the tables of a test resource, with the flags of [`TableExtractor::extract_tables`]
+pub fn get_tables(
+    filename: &str,
+    auto_span: bool,
+    auto_pad: bool,
+    extract_context: bool,
+) -> Result<Vec<Table>> {
+    let doc = get_doc(filename)?;
+    table_extractor(SpanMode::Default, false, false).extract_tables(
+        &doc,
+        auto_span,
+        auto_pad,
+        extract_context,
+    )
+}
+
+/// Extract the tables of an HTML page at `https://example.org/page`, without padding them or
+/// extracting their context. Tables are spanned with the given mode, or not spanned if it is None.
+/// Like browsers, errors in the HTML (e.g., invalid spans) are only forgiven in the browser mode.
+pub fn extract_tables(html: &str, span_mode: Option<SpanMode>) -> Result<Vec<Table>> {
+    let doc = Document::new("https://example.org/page".to_owned(), html.to_owned());
+    table_extractor(
+        span_mode.unwrap_or(SpanMode::Default),
+        span_mode == Some(SpanMode::Browser),
+        false,
+    )
+    .extract_tables(&doc, span_mode.is_some(), false, false)
+}
@@ -1 +1,7 @@
+pub mod test_format;
+#[cfg(feature = "arrow")]
+pub mod test_record_batch;
+pub mod test_rich_text;
 pub mod test_table;
+pub mod test_table_io;
+pub mod test_value;
@@ -0,0 +1,179 @@
+use crate::get_tables;
+use anyhow::Result;
+use rsoup::{
+    error::RSoupError,
+    models::{
+        content_hierarchy::ContentHierarchy,
+        format::{DataKind, MAGIC},
+        rich_text::{RichText, RichTextElement},
+        table::{Cell, Row, RowSection, Table},
+    },
+};
+use std::{fs, path::Path};
+
+#[test]
+fn test_versioned_format() -> Result<()> {
+    let table = &get_tables("table_span.html", true, false, true)?[0];
+
+    let bytes = table.to_bytes()?;
+    assert_eq!(bytes[..MAGIC.len()], MAGIC);
+    assert_eq!(bytes[MAGIC.len()], b'T');
+    assert_eq!(
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        DataKind::Table.current_version()
+    );
+    let table_prime = Table::from_bytes(&bytes)?;
+    assert_eq!(
+        serde_json::to_value(&table_prime)?,
+        serde_json::to_value(table)?
+    );
+
+    let text = &table.rows[0].cells[0].value;
+    assert_eq!(&RichText::from_bytes(&text.to_bytes()?)?, text);
+    let context = &table.context[0];
+    assert_eq!(
+        serde_json::to_value(ContentHierarchy::from_bytes(&context.to_bytes()?)?)?,
+        serde_json::to_value(context)?
+    );
+
+    // data of another kind
+    assert!(matches!(
+        Table::from_bytes(&text.to_bytes()?),
+        Err(RSoupError::InvalidFormatError {
+            kind: "Table",
+            version: 1,
+            ..
+        })
+    ));
+
+    // data written by a newer version
+    let mut newer_bytes = bytes.clone();
+    newer_bytes[4..6].copy_from_slice(&(DataKind::Table.current_version() + 1).to_le_bytes());
+    let err = Table::from_bytes(&newer_bytes).unwrap_err();
+    assert!(matches!(
+        err,
+        RSoupError::UnsupportedFormatVersionError { kind: "Table", .. }
+    ));
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "UnsupportedFormatVersionError: Table data has format version {} but this version of rsoup only supports up to version {}",
+            DataKind::Table.current_version() + 1,
+            DataKind::Table.current_version()
+        )
+    );
+
+    // truncated data
+    assert!(matches!(
+        Table::from_bytes(&bytes[..bytes.len() / 2]),
+        Err(RSoupError::InvalidFormatError { kind: "Table", .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_migrate_legacy_format() -> Result<()> {
+    let table = &get_tables("table_span.html", true, false, true)?[0];
+
+    // table written before the format had a header (version 1)
+    let b64s = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/resources/legacy/table_span.v1.b64"),
+    )?;
+    let legacy_table = Table::from_base64(b64s.trim())?;
+
+    assert_eq!(legacy_table.to_list(), table.to_list());
+    assert_eq!(legacy_table.context.len(), table.context.len());
+    assert!(legacy_table
+        .rows
+        .iter()
+        .all(|row| row.section == RowSection::TBody));
+    assert!(legacy_table
+        .rows
+        .iter()
+        .flat_map(|row| row.cells.iter())
+        .all(|cell| cell.origin.is_none() && !cell.is_span_copy && cell.nested_tables.is_empty()));
+    assert!(legacy_table.parent_id.is_none() && legacy_table.header.is_none());
+
+    // migrated tables are written in the current version
+    let bytes = legacy_table.to_bytes()?;
+    assert_eq!(Table::from_bytes(&bytes)?.to_list(), table.to_list());
+
+    // table written before rows had the index of their row group (version 2), the groups are
+    // the runs of rows of the same section
+    let b64s = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/resources/legacy/table_groups.v2.b64"),
+    )?;
+    let legacy_table = Table::from_base64(b64s.trim())?;
+    assert_eq!(
+        legacy_table.to_list(),
+        vec![
+            vec!["h", "i"],
+            vec!["a", "b"],
+            vec!["c", "d"],
+            vec!["e", "f"]
+        ]
+    );
+    assert_eq!(
+        legacy_table
+            .rows
+            .iter()
+            .map(|r| (r.section, r.group))
+            .collect::<Vec<_>>(),
+        vec![
+            (RowSection::THead, 0),
+            (RowSection::TBody, 1),
+            (RowSection::TBody, 1),
+            (RowSection::TFoot, 2)
+        ]
+    );
+    assert_eq!(legacy_table.get_cell(1, 0).unwrap().rowspan, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_serialize_parts_of_table() -> Result<()> {
+    let table = &get_tables("table_span.html", true, false, true)?[0];
+    let row = &table.rows[1];
+    let cell = &row.cells[1];
+    let text = &cell.value;
+    let element = text.element.get_root();
+    let context = &table.context[0];
+
+    assert_eq!(
+        serde_json::to_value(Row::from_json(&row.to_json()?)?)?,
+        serde_json::to_value(row)?
+    );
+    assert_eq!(
+        serde_json::to_value(Row::from_bytes(&row.to_bytes()?)?)?,
+        serde_json::to_value(row)?
+    );
+    assert_eq!(
+        serde_json::to_value(Cell::from_json(&cell.to_json()?)?)?,
+        serde_json::to_value(cell)?
+    );
+    assert_eq!(
+        serde_json::to_value(Cell::from_bytes(&cell.to_bytes()?)?)?,
+        serde_json::to_value(cell)?
+    );
+    assert_eq!(&RichText::from_json(&text.to_json()?)?, text);
+    assert_eq!(&RichTextElement::from_json(&element.to_json()?)?, element);
+    assert_eq!(&RichTextElement::from_bytes(&element.to_bytes()?)?, element);
+    assert_eq!(
+        serde_json::to_value(ContentHierarchy::from_json(&context.to_json()?)?)?,
+        serde_json::to_value(context)?
+    );
+
+    // each kind of data is only decoded as itself
+    assert!(matches!(
+        Cell::from_bytes(&row.to_bytes()?),
+        Err(RSoupError::InvalidFormatError { kind: "Cell", .. })
+    ));
+    assert!(matches!(
+        Row::from_json("{\"cells\": 1}"),
+        Err(RSoupError::SerdeJsonError(_))
+    ));
+
+    Ok(())
+}
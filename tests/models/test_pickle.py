@@ -1,6 +1,7 @@
 from __future__ import annotations
 
 from pathlib import Path
+import copy
 import pickle
 import pytest
 from rsoup.core import ContextExtractor, RichText, Table, TableExtractor, ContentHierarchy
@@ -34,4 +35,14 @@ def test_rich_text_pickle(tables: list[Table]):
         for row in t.rows:
             for cell in row.cells:
                 cell_prime: RichText = pickle.loads(pickle.dumps(cell.value))
-                assert cell.value.to_dict() == cell_prime.to_dict()
\ No newline at end of file
+                assert cell.value.to_dict() == cell_prime.to_dict()
+
+def test_cell_value_is_read_only(tables: list[Table]):
+    cell = tables[0].rows[0].cells[0]
+    with pytest.raises(TypeError):
+        cell.value.set_element_attr_by_id(0, "class", "x")
+
+    value = copy.copy(cell.value)
+    value.set_element_attr_by_id(0, "class", "x")
+    cell.value = value
+    assert cell.value.get_element_attr_by_id(0, "class") == "x"
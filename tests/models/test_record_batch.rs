@@ -0,0 +1,68 @@
+use crate::get_tables;
+use anyhow::Result;
+use arrow::array::{Array, BooleanArray, StringArray, UInt16Array, UInt32Array};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rsoup::models::table::record_batch;
+
+#[test]
+fn test_to_record_batch() -> Result<()> {
+    let tables = get_tables("table_span.html", true, true, false)?;
+    let n_cells = tables
+        .iter()
+        .flat_map(|t| t.rows.iter())
+        .map(|r| r.cells.len())
+        .sum::<usize>();
+
+    let batch = record_batch::to_record_batch(&tables)?;
+    assert_eq!(batch.num_rows(), n_cells);
+    assert_eq!(
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "table_id",
+            "row",
+            "col",
+            "text",
+            "is_header",
+            "rowspan",
+            "colspan",
+            "html"
+        ]
+    );
+
+    let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+    let table_ids = column("table_id");
+    let table_ids = table_ids.as_any().downcast_ref::<StringArray>().unwrap();
+    let rows = column("row");
+    let rows = rows.as_any().downcast_ref::<UInt32Array>().unwrap();
+    let cols = column("col");
+    let cols = cols.as_any().downcast_ref::<UInt32Array>().unwrap();
+    let texts = column("text");
+    let texts = texts.as_any().downcast_ref::<StringArray>().unwrap();
+    let is_headers = column("is_header");
+    let is_headers = is_headers.as_any().downcast_ref::<BooleanArray>().unwrap();
+    let colspans = column("colspan");
+    let colspans = colspans.as_any().downcast_ref::<UInt16Array>().unwrap();
+
+    // "Height (rounded)" spans two columns, its copy keeps the span of the merged cell
+    assert_eq!(table_ids.value(2), tables[0].id);
+    assert_eq!((rows.value(2), cols.value(2)), (0, 2));
+    assert_eq!(texts.value(2), "Height (rounded)");
+    assert!(is_headers.value(2));
+    assert_eq!(colspans.value(2), 2);
+
+    // parquet roundtrip
+    let path = std::env::temp_dir().join("rsoup_test_to_record_batch.parquet");
+    record_batch::write_parquet(&tables, std::fs::File::create(&path)?)?;
+    let batches = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path)?)?
+        .build()?
+        .collect::<Result<Vec<_>, _>>()?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(batches, vec![batch]);
+
+    Ok(())
+}
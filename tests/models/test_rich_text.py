@@ -0,0 +1,46 @@
+from rsoup.core import RichText, TableExtractor, ContextExtractor
+
+
+def get_cell_text(html: str) -> RichText:
+    extractor = TableExtractor(context_extractor=ContextExtractor())
+    tables = extractor.extract(
+        "https://example.org/page",
+        html,
+        auto_span=False,
+        auto_pad=False,
+        extract_context=False,
+    )
+    return tables[0].get_cell(0, 0).value
+
+
+def test_offsets_in_code_points():
+    text = get_cell_text("<table><tr><td>Zürich <b>Genève</b></td></tr></table>")
+    assert text.text == "Zürich Genève"
+    assert text.len() == len(text.text) == 13
+    assert text.byte_len() == len(text.text.encode()) == 15
+
+    (id,) = [id for id in text.iter_element_id() if text.get_element_tag_by_id(id) == "b"]
+    element = text.get_element_by_id(id)
+    assert (element.start, element.end) == (7, 13)
+    assert text.text[element.start : element.end] == "Genève"
+    element = text.get_element_by_id(id, unit="byte")
+    assert (element.start, element.end) == (8, 15)
+
+    # dictionaries store offsets in bytes like the other serializations
+    obj = text.to_dict()
+    assert obj["offset_unit"] == "byte"
+    assert [(node["tag"], node["start"], node["end"]) for node in obj["element"]["nodes"]] == [
+        ("td", 0, 15),
+        ("b", 8, 15),
+    ]
+    text_prime = RichText.from_dict(obj)
+    assert text_prime.to_dict() == obj
+    assert text_prime.to_html() == text.to_html()
+
+    # dictionaries without "offset_unit" are in bytes, others are converted from their unit
+    del obj["offset_unit"]
+    assert RichText.from_dict(obj).to_html() == text.to_html()
+    obj["offset_unit"] = "codepoint"
+    obj["element"]["nodes"][1].update(start=7, end=13)
+    obj["element"]["nodes"][0].update(end=13)
+    assert RichText.from_dict(obj).to_html() == text.to_html()
@@ -0,0 +1,336 @@
+use crate::{extract_tables, get_tables};
+use anyhow::Result;
+use hashbrown::HashSet;
+use proptest::prelude::*;
+use rsoup::{
+    extractors::text::get_rich_text,
+    misc::escape_html,
+    models::{
+        rich_text::{OffsetUnit, RichText},
+        selector::RichTextSelector,
+    },
+};
+
+/// Rich text of the first cell of the first table of the HTML
+fn get_cell_text(html: &str) -> Result<RichText> {
+    Ok(extract_tables(html, None)?[0].rows[0].cells[0]
+        .value
+        .clone())
+}
+
+/// Tags and text of the elements of a rich text, in preorder
+fn get_elements(text: &RichText) -> Vec<(String, String)> {
+    text.element
+        .iter_id_preorder()
+        .map(|id| {
+            let el = text.element.get_node(*id);
+            (el.tag.clone(), text.text[el.start..el.end].to_owned())
+        })
+        .collect()
+}
+
+#[test]
+fn test_remove_elements() -> Result<()> {
+    let text = get_cell_text(
+        r##"<table><tr><td><a href="/Everest">Everest</a><sup class="reference"><a href="#n1">[1]</a></sup> (<b>Nepal</b><sup class="reference">[2]</sup>)</td></tr></table>"##,
+    )?;
+    assert_eq!(text.text, "Everest[1] (Nepal[2])");
+
+    let selector = "sup.reference".parse::<RichTextSelector>().unwrap();
+    let removed = text.remove_elements(&selector);
+    assert!(removed.validate());
+    assert_eq!(removed.text, "Everest (Nepal)");
+    assert_eq!(
+        get_elements(&removed),
+        vec![
+            ("td".to_owned(), "Everest (Nepal)".to_owned()),
+            ("a".to_owned(), "Everest".to_owned()),
+            ("b".to_owned(), "Nepal".to_owned()),
+        ]
+    );
+
+    // descendant and attribute selectors only match the link of the first footnote
+    let selector = r##"sup a[href^="#"]"##.parse::<RichTextSelector>().unwrap();
+    let removed = text.remove_elements(&selector);
+    assert!(removed.validate());
+    assert_eq!(removed.text, "Everest (Nepal[2])");
+
+    let removed = text.remove_elements_by(|el| el.tag == "a");
+    assert!(removed.validate());
+    assert_eq!(removed.text, " (Nepal[2])");
+
+    assert!("sup >".parse::<RichTextSelector>().is_err());
+    assert!("a[href".parse::<RichTextSelector>().is_err());
+
+    // footnotes in the header of a wikipedia table
+    let tables = get_tables(
+        "wikipedia/List_of_highest_mountains_on_Earth.html",
+        false,
+        false,
+        false,
+    )?;
+    let selector = "sup.reference".parse::<RichTextSelector>().unwrap();
+    let cell = &tables[2].rows[0].cells[2].value;
+    assert_eq!(cell.text, "Height\n(rounded)\n[dp 2]");
+    let removed = cell.remove_elements(&selector);
+    assert!(removed.validate());
+    assert_eq!(removed.text, "Height\n(rounded)\n");
+    Ok(())
+}
+
+#[test]
+fn test_edit_rich_text() -> Result<()> {
+    let text = get_cell_text(
+        r#"<table><tr><td><a href="/Everest">Mount Everest</a>, <b>Nepal</b></td></tr></table>"#,
+    )?;
+    let elements = |text: &RichText| {
+        assert!(text.validate());
+        get_elements(text)
+            .into_iter()
+            .map(|(tag, text)| format!("{}:{}", tag, text))
+            .collect::<Vec<_>>()
+    };
+
+    let sliced = text.slice(6, 17)?;
+    assert_eq!(sliced.text, "Everest, Ne");
+    assert_eq!(elements(&sliced), ["td:Everest, Ne", "a:Everest", "b:Ne"]);
+    assert_eq!(elements(&text.slice(13, 15)?), ["td:, "]);
+
+    let replaced = text.replace(0..5, "Mt.")?;
+    assert_eq!(
+        elements(&replaced),
+        ["td:Mt. Everest, Nepal", "a:Mt. Everest", "b:Nepal"]
+    );
+    let replaced = text.replace(15..20, "China")?;
+    assert_eq!(
+        elements(&replaced),
+        ["td:Mount Everest, China", "a:Mount Everest", "b:China"]
+    );
+    // elements inside the range are removed, elements overlapping it are clipped
+    assert_eq!(
+        elements(&text.replace(13..20, "")?),
+        ["td:Mount Everest", "a:Mount Everest"]
+    );
+    assert_eq!(
+        elements(&text.replace(10..17, " ")?),
+        ["td:Mount Ever pal", "a:Mount Ever", "b:pal"]
+    );
+
+    let inserted = text.insert(13, " (8,848 m)")?;
+    assert_eq!(
+        elements(&inserted),
+        [
+            "td:Mount Everest (8,848 m), Nepal",
+            "a:Mount Everest",
+            "b:Nepal"
+        ]
+    );
+
+    let concatenated = text.slice(0, 13)?.concat(&RichText::from_str(" and K2"));
+    assert_eq!(
+        elements(&concatenated),
+        [
+            ":Mount Everest and K2",
+            "td:Mount Everest",
+            "a:Mount Everest"
+        ]
+    );
+    assert_eq!(
+        elements(&RichText::from_str("a").concat(&text)),
+        [
+            ":aMount Everest, Nepal",
+            "td:Mount Everest, Nepal",
+            "a:Mount Everest",
+            "b:Nepal"
+        ]
+    );
+
+    assert!(text.slice(3, 30).is_err());
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 5..3;
+    assert!(text.replace(reversed, "").is_err());
+    assert!(RichText::from_str("Café").slice(0, 4).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_select_elements() -> Result<()> {
+    let text = get_cell_text(
+        r##"<table><tr><td class="name"><a href="/Everest">Mount <i>Everest</i></a><sup class="reference"><a href="#n1">[1]</a></sup> and <a>K2</a></td></tr></table>"##,
+    )?;
+    assert_eq!(text.text, "Mount Everest[1] and K2");
+    let select = |query: &str| {
+        let selector = query.parse::<RichTextSelector>().unwrap();
+        text.select(&selector)
+            .into_iter()
+            .map(|id| {
+                let el = text.element.get_node(id);
+                text.text[el.start..el.end].to_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select("a"), ["Mount Everest", "[1]", "K2"]);
+    assert_eq!(select("a[href]"), ["Mount Everest", "[1]"]);
+    assert_eq!(select(r#"a[href$="/Everest"] i"#), ["Everest"]);
+    assert_eq!(select("td > a"), ["Mount Everest", "K2"]);
+    assert_eq!(select("sup.reference a, i"), ["Everest", "[1]"]);
+    assert_eq!(select(".name"), ["Mount Everest[1] and K2"]);
+    assert!(select("b").is_empty());
+
+    let tags = |offset: usize| {
+        text.elements_at(offset)
+            .into_iter()
+            .map(|id| text.get_element_tag_by_id(id))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(tags(0), ["td", "a"]);
+    assert_eq!(tags(8), ["td", "a", "i"]);
+    assert_eq!(tags(14), ["td", "sup", "a"]);
+    assert_eq!(tags(17), ["td"]);
+    assert!(tags(23).is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_offset_units() -> Result<()> {
+    let text = get_cell_text(
+        r#"<table><tr><td>Zürich 😀 <a href="/Genève">Genève</a></td></tr></table>"#,
+    )?;
+    let link = text.get_element_by_id(text.select(&"a".parse().unwrap())[0]);
+    assert_eq!(&text.text[link.start..link.end], "Genève");
+    assert_eq!((link.start, link.end), (13, 20));
+
+    let convert = |offset: usize, unit: OffsetUnit| text.from_byte_offset(offset, unit).unwrap();
+    assert_eq!(convert(link.start, OffsetUnit::CodePoint), 9);
+    assert_eq!(convert(link.end, OffsetUnit::CodePoint), 15);
+    assert_eq!(convert(link.start, OffsetUnit::Utf16), 10);
+    assert_eq!(convert(link.end, OffsetUnit::Utf16), 16);
+
+    assert_eq!(text.to_byte_offset(9, OffsetUnit::CodePoint)?, 13);
+    assert_eq!(text.to_byte_offset(16, OffsetUnit::Utf16)?, 20);
+    assert_eq!(text.to_byte_offset(0, OffsetUnit::Utf16)?, 0);
+    assert_eq!(
+        text.convert_offset(9, OffsetUnit::CodePoint, OffsetUnit::Utf16)?,
+        10
+    );
+    // in the middle of a character or out of the text
+    assert!(text.from_byte_offset(2, OffsetUnit::CodePoint).is_err());
+    assert!(text.to_byte_offset(8, OffsetUnit::Utf16).is_err());
+    assert!(text.to_byte_offset(16, OffsetUnit::CodePoint).is_err());
+    Ok(())
+}
+
+/// Rich text of the first element of an HTML fragment, as extracted by the text extractor
+fn parse_rich_text(html: &str) -> RichText {
+    let doc = scraper::Html::parse_fragment(html);
+    let el = doc
+        .tree
+        .root()
+        .first_child()
+        .unwrap()
+        .first_child()
+        .unwrap();
+    get_rich_text(
+        &el,
+        &HashSet::new(),
+        false,
+        &HashSet::new(),
+        &HashSet::new(),
+    )
+}
+
+#[test]
+fn test_empty_element_before_skipped_space() {
+    // the leading space of <b> is skipped, so <b> starts after it, and so must the empty <i>
+    // before the space, otherwise it starts before its parent and cannot be rendered
+    let text = parse_rich_text("<p>a<b><i></i> c</b></p>");
+    assert_eq!(text.text, "a c");
+    assert_eq!(
+        text.element
+            .iter()
+            .iter()
+            .map(|el| (el.tag.as_str(), el.start, el.end))
+            .collect::<Vec<_>>(),
+        vec![("p", 0, 3), ("b", 2, 3), ("i", 2, 2)]
+    );
+    assert_eq!(text.to_html(false, false), "a <b><i></i>c</b>");
+}
+
+#[test]
+fn test_to_html_escaping() {
+    let text = parse_rich_text(
+        r#"<p>a &lt; b &amp;&amp; c &gt; d <a href="/q?x=1&amp;y=&quot;2&quot;" title="it's">"link"</a></p>"#,
+    );
+    assert_eq!(text.text, r#"a < b && c > d "link""#);
+    assert_eq!(
+        text.to_html(true, true),
+        r#"<p>a &lt; b &amp;&amp; c &gt; d <a href="/q?x=1&amp;y=&quot;2&quot;" title="it's">"link"</a></p>"#
+    );
+    assert_eq!(
+        text.to_html_with_attrs(false, Some(&["href"])),
+        r#"a &lt; b &amp;&amp; c &gt; d <a href="/q?x=1&amp;y=&quot;2&quot;">"link"</a>"#
+    );
+    assert_eq!(
+        text.to_html(false, false),
+        r#"a &lt; b &amp;&amp; c &gt; d <a>"link"</a>"#
+    );
+}
+
+#[test]
+fn test_to_markdown() {
+    let text = parse_rich_text(
+        r#"<div><p><b>Mount <i>Everest</i></b> is <a href="/wiki/Nepal">in <em>Nepal</em></a>, 8,849 m<sup>[1]</sup>
+        <br>run <code>a_b `c`</code> as *root*</p><p>H<sub>2</sub>O, <strong> </strong><a href="/wiki/Nepal">Nepal</a> and
+        <a href="/wiki/(China)">China</a></p></div>"#,
+    );
+    assert_eq!(
+        text.text,
+        "Mount Everest is in Nepal, 8,849 m[1]\nrun a_b `c` as *root*\nH2O, Nepal and China"
+    );
+    assert_eq!(
+        text.to_markdown(),
+        "**Mount _Everest_** is [in _Nepal_](/wiki/Nepal), 8,849 m<sup>\\[1\\]</sup>\\\n\
+         run `` a_b `c` `` as \\*root\\*\\\n\
+         H<sub>2</sub>O, [Nepal](/wiki/Nepal) and [China](</wiki/(China)>)"
+    );
+    assert_eq!(
+        text.to_text_with_links(),
+        "Mount Everest is in Nepal[1], 8,849 m[1]\nrun a_b `c` as *root*\nH2O, Nepal[1] and China[2]\n\
+         \n[1]: /wiki/Nepal\n[2]: /wiki/(China)"
+    );
+    assert_eq!(RichText::from_str("a_b").to_text_with_links(), "a_b");
+}
+
+/// Random HTML content made of inline elements with attributes, and text with characters
+/// that must be escaped
+fn arb_html_content() -> impl Strategy<Value = String> {
+    let text = "[a-zA-Z0-9<>&\"'éü ]{1,8}".prop_map(|text| escape_html(&text));
+    text.prop_recursive(4, 32, 3, |inner| {
+        (
+            prop::sample::select(vec!["a", "b", "i", "span", "sup", "code"]),
+            prop::option::of("[a-z0-9<>&\"'/ ]{0,6}"),
+            prop::option::of("[a-z]{1,5}"),
+            prop::collection::vec(inner, 1..4),
+        )
+            .prop_map(|(tag, href, class, children)| {
+                let mut html = format!("<{}", tag);
+                if let Some(href) = href {
+                    html.push_str(&format!(" href=\"{}\"", escape_html(&href)));
+                }
+                if let Some(class) = class {
+                    html.push_str(&format!(" class=\"{}\"", class));
+                }
+                format!("{}>{}</{}>", html, children.concat(), tag)
+            })
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_to_html_round_trip(content in prop::collection::vec(arb_html_content(), 1..4)) {
+        let text = parse_rich_text(&format!("<p>{}</p>", content.concat()));
+        let html = text.to_html(true, true);
+        prop_assert_eq!(parse_rich_text(&html), text, "html: {}", html);
+    }
+}
@@ -1,33 +1,20 @@
-use crate::get_doc;
+use crate::{extract_tables, get_tables, table_extractor};
 use anyhow::Result;
-use pyo3::Python;
 use rsoup::{
-    extractors::{context_v1::ContextExtractor, table::TableExtractor},
-    models::table::Table,
+    error::RSoupError,
+    extractors::Document,
+    models::table::{CellFormat, CellOrigin, SpanMode, Table, TableHeader, ValueParser},
 };
 
-fn get_tables(filename: &str) -> Result<Vec<Table>> {
-    let gil = Python::acquire_gil();
-    let py = gil.python();
-
-    let extractor = TableExtractor::new(ContextExtractor::default(), None, None, None, true, false);
-    let doc = get_doc(filename)?;
-
-    Ok(extractor.extract_tables(py, &doc, false, false, false)?)
-}
-
 #[test]
 fn test_span() -> Result<()> {
-    let gil = Python::acquire_gil();
-    let py = gil.python();
-
-    let tables = get_tables("table_span.html")?;
+    let tables = get_tables("table_span.html", false, false, false)?;
     let t0 = &tables[0];
 
-    let t0prime = t0.span(py)?;
+    let t0prime = t0.span()?;
 
     assert_eq!(
-        t0prime.to_list(py)?,
+        t0prime.to_list(),
         vec![
             vec![
                 "Mountain name(s)",
@@ -74,3 +61,480 @@ fn test_span() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_span_errors() -> Result<()> {
+    let html = r#"<table>
+            <tr><td>a</td><td rowspan="2">b</td></tr>
+            <tr><td colspan="2">c</td><td>d</td></tr>
+        </table>"#;
+    let tables = extract_tables(html, None)?;
+    match tables[0].span() {
+        Err(RSoupError::OverlapSpanError {
+            table_id,
+            row,
+            col,
+            rowspan,
+            colspan,
+        }) => {
+            assert_eq!(table_id, "https://example.org/page?table_no=0");
+            assert_eq!((row, col, rowspan, colspan), (1, 0, 1, 2));
+        }
+        res => panic!(
+            "expected OverlapSpanError, got {:?}",
+            res.map(|t| t.to_list())
+        ),
+    }
+
+    let html = r#"<table>
+            <tr><td colspan="3">a</td><td>b</td></tr>
+            <tr><td>c</td></tr>
+        </table>"#;
+    let tables = extract_tables(html, None)?;
+    match tables[0].span() {
+        Err(RSoupError::InvalidCellSpanError {
+            table_id,
+            row,
+            col,
+            rowspan,
+            colspan,
+        }) => {
+            assert_eq!(table_id, "https://example.org/page?table_no=0");
+            assert_eq!((row, col, rowspan, colspan), (0, 0, 1, 3));
+        }
+        res => panic!(
+            "expected InvalidCellSpanError, got {:?}",
+            res.map(|t| t.to_list())
+        ),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_span_provenance() -> Result<()> {
+    let html = r#"<table>
+            <tr><th rowspan="2">name</th><th colspan="2">height</th></tr>
+            <tr><th>m</th><th>ft</th></tr>
+            <tr><td>Everest</td><td>8,848</td><td>29,029</td></tr>
+        </table>"#;
+    let tables = extract_tables(html, None)?;
+    let table = tables[0].span()?;
+
+    let provenance = |ri: usize, ci: usize| {
+        let cell = table.get_cell(ri, ci).unwrap();
+        let origin = cell.origin.unwrap();
+        (
+            (origin.row, origin.col, origin.rowspan, origin.colspan),
+            cell.is_span_copy,
+        )
+    };
+
+    assert_eq!(provenance(0, 0), ((0, 0, 2, 1), false));
+    assert_eq!(provenance(1, 0), ((0, 0, 2, 1), true));
+    assert_eq!(provenance(0, 1), ((0, 1, 1, 2), false));
+    assert_eq!(provenance(0, 2), ((0, 1, 1, 2), true));
+    assert_eq!(provenance(1, 2), ((1, 2, 1, 1), false));
+    assert_eq!(provenance(2, 1), ((2, 1, 1, 1), false));
+
+    // spanning again keeps the provenance
+    let table2 = table.span()?;
+    let cell = table2.get_cell(1, 0).unwrap();
+    assert_eq!(
+        cell.origin,
+        Some(CellOrigin {
+            row: 0,
+            col: 0,
+            rowspan: 2,
+            colspan: 1
+        })
+    );
+    assert!(cell.is_span_copy);
+
+    Ok(())
+}
+
+#[test]
+fn test_span_browser() -> Result<()> {
+    let extract = |html: &str| -> Result<Table> {
+        Ok(extract_tables(html, Some(SpanMode::Browser))?.remove(0))
+    };
+
+    // over-wide colspan that is not in the last column
+    let table = extract(
+        r#"<table>
+            <tr><td colspan="3">a</td><td>b</td></tr>
+            <tr><td>c</td></tr>
+        </table>"#,
+    )?;
+    assert_eq!(
+        table.to_list(),
+        vec![vec!["a", "a", "a", "b"], vec!["c", "", "", ""]]
+    );
+
+    // rowspan = 0 spans to the end of the row group
+    let table = extract(
+        r#"<table>
+            <thead>
+                <tr><th rowspan="0">h</th><th>x</th></tr>
+                <tr><th>y</th></tr>
+            </thead>
+            <tbody><tr><td>1</td><td>2</td></tr></tbody>
+        </table>"#,
+    )?;
+    assert_eq!(
+        table.to_list(),
+        vec![vec!["h", "x"], vec!["h", "y"], vec!["1", "2"]]
+    );
+    let cell = table.get_cell(1, 0).unwrap();
+    assert!(cell.is_span_copy);
+    assert_eq!(cell.origin.unwrap().rowspan, 0);
+
+    // overlapping cells keep the cell placed first
+    let table = extract(
+        r#"<table>
+            <tr><td>a</td><td rowspan="2">b</td></tr>
+            <tr><td colspan="2">c</td><td>d</td></tr>
+        </table>"#,
+    )?;
+    assert_eq!(
+        table.to_list(),
+        vec![vec!["a", "b", ""], vec!["c", "b", "d"]]
+    );
+
+    // rowspan beyond the last row is clamped to the row group, colspan is clamped to 1000
+    let table = extract(
+        r#"<table>
+            <tr><td rowspan="3">a</td><td colspan="100000">b</td></tr>
+        </table>"#,
+    )?;
+    assert_eq!(table.shape(), (1, 1001));
+    assert_eq!(table.get_cell(0, 0).unwrap().value.text, "a");
+    assert_eq!(table.get_cell(0, 1000).unwrap().value.text, "b");
+
+    // spans do not cross adjacent row groups of the same section
+    let table = extract(
+        r#"<table>
+            <tbody><tr><td rowspan="2">a</td><td>b</td></tr></tbody>
+            <tbody><tr><td>c</td><td>d</td></tr></tbody>
+        </table>"#,
+    )?;
+    assert_eq!(table.to_list(), vec![vec!["a", "b"], vec!["c", "d"]]);
+    assert_eq!(
+        table.rows.iter().map(|r| r.group).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+
+    let table = extract(
+        r#"<table>
+            <tbody>
+                <tr><td rowspan="0">a</td><td>b</td></tr>
+                <tr><td>c</td></tr>
+            </tbody>
+            <tbody><tr><td>d</td><td>e</td></tr></tbody>
+        </table>"#,
+    )?;
+    assert_eq!(
+        table.to_list(),
+        vec![vec!["a", "b"], vec!["a", "c"], vec!["d", "e"]]
+    );
+
+    // the row groups are written back as they are
+    assert_eq!(extract(&table.to_html(true))?.to_list(), table.to_list());
+
+    // spans that overflow are clamped, and never add rows to the table
+    let table = extract(
+        r#"<table>
+            <tr><td rowspan="99999">a</td></tr>
+            <tr><td>b</td></tr>
+        </table>"#,
+    )?;
+    assert_eq!(table.to_list(), vec![vec!["a", ""], vec!["a", "b"]]);
+    let table = extract(r#"<table><tr><td rowspan="65534" colspan="1000">a</td></tr></table>"#)?;
+    assert_eq!(table.shape(), (1, 1000));
+
+    // unlike the browser mode, the default mode ignores spans that overflow
+    let doc = Document::new(
+        "https://example.org/page".to_owned(),
+        r#"<table><tr><td rowspan="99999" colspan="99999">a</td></tr></table>"#.to_owned(),
+    );
+    let tables =
+        table_extractor(SpanMode::Default, true, false).extract_tables(&doc, true, false, false)?;
+    assert_eq!(tables[0].shape(), (1, 1));
+
+    Ok(())
+}
+
+#[test]
+fn test_detect_headers() -> Result<()> {
+    let detect = |html: &str| -> Result<TableHeader> {
+        let mut tables = extract_tables(html, Some(SpanMode::Default))?;
+        let header = tables[0].detect_headers();
+        assert_eq!(tables[0].header, Some(header));
+        Ok(header)
+    };
+    let header = |n_rows, n_cols| TableHeader { n_rows, n_cols };
+
+    let mut tables = get_tables("table_span.html", false, false, false)?;
+    let mut table = tables.remove(0).span()?;
+    assert_eq!(table.detect_headers(), header(3, 0));
+
+    // thead
+    assert_eq!(
+        detect(
+            r#"<table>
+                <thead><tr><td>name</td><td>country</td></tr></thead>
+                <tbody><tr><td>Everest</td><td>Nepal</td></tr></tbody>
+            </table>"#
+        )?,
+        header(1, 0)
+    );
+
+    // bold-only content and row headers
+    assert_eq!(
+        detect(
+            r#"<table>
+                <tr><td><b>name</b></td><td><strong>height</strong> <b>(m)</b></td></tr>
+                <tr><th>Everest</th><td>8,848</td></tr>
+                <tr><th>K2</th><td>8,611</td></tr>
+            </table>"#
+        )?,
+        header(1, 1)
+    );
+
+    // type difference and scope
+    assert_eq!(
+        detect(
+            r#"<table>
+                <tr><td>year</td><td>name</td><td>votes</td></tr>
+                <tr><td scope="row">2016</td><td>A</td><td>1,234</td></tr>
+                <tr><td scope="row">2020</td><td>B</td><td>−56</td></tr>
+            </table>"#
+        )?,
+        header(1, 1)
+    );
+
+    // numbers are recognized like the values of the cells
+    assert_eq!(
+        detect(
+            r#"<table>
+                <tr><td>party</td><td>share</td></tr>
+                <tr><td>A</td><td>12 %</td></tr>
+                <tr><td>B</td><td>7.5%</td></tr>
+            </table>"#
+        )?,
+        header(1, 0)
+    );
+    assert_eq!(
+        detect(
+            r#"<table>
+                <tr><td>name</td><td>1,2,3</td></tr>
+                <tr><td>A</td><td>1,234</td></tr>
+                <tr><td>B</td><td>$5</td></tr>
+            </table>"#
+        )?,
+        header(1, 0)
+    );
+
+    // no header
+    assert_eq!(
+        detect(
+            r#"<table>
+                <tr><td>a</td><td>b</td></tr>
+                <tr><td>c</td><td>d</td></tr>
+            </table>"#
+        )?,
+        header(0, 0)
+    );
+
+    // numbers are parsed with the conventions of the locale of the parser
+    let table = extract_tables(
+        r#"<table>
+            <tr><td>Name</td><td>Wert</td></tr>
+            <tr><td>A</td><td>1.234,5</td></tr>
+            <tr><td>B</td><td>7,25</td></tr>
+        </table>"#,
+        Some(SpanMode::Default),
+    )?
+    .remove(0);
+    assert_eq!(TableHeader::detect(&table), header(0, 0));
+    let de = ValueParser::from_locale("de").unwrap();
+    assert_eq!(TableHeader::detect_with_parser(&table, &de), header(1, 0));
+
+    Ok(())
+}
+
+#[test]
+fn test_flatten_headers() -> Result<()> {
+    let tables = get_tables("table_span.html", false, false, false)?;
+    let table = tables[0].span()?;
+    let flattened_table = table.flatten_headers(None);
+
+    assert_eq!(
+        flattened_table.column_names(" / "),
+        vec![
+            "Mountain name(s)",
+            "Height (rounded) / m",
+            "Height (rounded) / ft",
+            "Range",
+            "Ascents before 2004 / 1st",
+            "Ascents before 2004 / successful / y",
+            "Ascents before 2004 / successful / n",
+            "Country",
+        ]
+    );
+    assert_eq!(
+        flattened_table.column_paths[5],
+        vec!["Ascents before 2004", "successful", "y"]
+    );
+    assert_eq!(flattened_table.rows.len(), 1);
+    assert_eq!(flattened_table.to_list()[0][1], "8,848");
+
+    // rows grouping the body rows (e.g., "District 1") are not header rows
+    let tables = get_tables(
+        "wikipedia/2016_Nova_Scotia_municipal_elections.html",
+        false,
+        false,
+        false,
+    )?;
+    let table = tables[4].span()?;
+    let flattened_table = table.flatten_headers(None);
+    assert_eq!(
+        flattened_table.column_names(" / "),
+        vec!["Candidate", "Vote", "%"]
+    );
+    assert_eq!(
+        flattened_table.to_list()[..2],
+        vec![
+            vec!["District 1", "District 1", "District 1"],
+            vec!["Clarence Prince (X)", "1,982", "49.00"],
+        ]
+    );
+
+    // explicit number of header rows
+    let flattened_table = table.flatten_headers(Some(2));
+    assert_eq!(
+        flattened_table.column_names("-"),
+        vec!["Candidate-District 1", "Vote-District 1", "%-District 1"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_to_csv() -> Result<()> {
+    let html = r#"<table>
+            <tr><th>Name</th><th>Note</th></tr>
+            <tr><td><a href="https://example.org/a">Smith, John</a></td><td>said "hi"</td></tr>
+            <tr><td>Doe</td><td>line 1<br>line 2</td></tr>
+        </table>"#;
+    let table = extract_tables(html, None)?[0].span()?;
+
+    assert_eq!(
+        table.to_csv(CellFormat::Text, true),
+        "Name,Note\r\n\"Smith, John\",\"said \"\"hi\"\"\"\r\nDoe,\"line 1\nline 2\"\r\n"
+    );
+    assert_eq!(
+        table.to_csv(CellFormat::Text, false),
+        "\"Smith, John\",\"said \"\"hi\"\"\"\r\nDoe,\"line 1\nline 2\"\r\n"
+    );
+    assert_eq!(
+        table.to_tsv(CellFormat::Markdown, false),
+        "[Smith, John](https://example.org/a)\t\"said \"\"hi\"\"\"\r\nDoe\t\"line 1\\\nline 2\"\r\n"
+    );
+    assert_eq!(
+        table.to_csv(CellFormat::Html, false).lines().next(),
+        Some("\"<a href=\"\"https://example.org/a\"\">Smith, John</a>\",\"said \"\"hi\"\"\"")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_to_html() -> Result<()> {
+    let html = r#"<table class="data"><caption>Heights &amp; ranges</caption>
+            <thead><tr><th rowspan="2">Name</th><th colspan="2">Height</th></tr>
+            <tr><th>m</th><th>ft</th></tr></thead>
+            <tbody><tr><td title='a "peak"'><b>Everest</b></td><td>8848</td><td>29029</td></tr></tbody>
+        </table>"#;
+    let table = extract_tables(html, None)?[0].clone();
+    let spanned_table = table.span()?;
+
+    let expected_html = concat!(
+        r#"<table class="data"><caption>Heights &amp; ranges</caption>"#,
+        r#"<thead><tr><th rowspan="2">Name</th><th colspan="2">Height</th></tr>"#,
+        r#"<tr><th>m</th><th>ft</th></tr></thead>"#,
+        r#"<tbody><tr><td title="a &quot;peak&quot;"><b>Everest</b></td><td>8848</td><td>29029</td></tr></tbody>"#,
+        r#"</table>"#
+    );
+    assert_eq!(table.to_html(false), expected_html);
+    // merged cells of a spanned table are written back once with their spans
+    assert_eq!(spanned_table.to_html(false), expected_html);
+    assert_eq!(
+        spanned_table.to_html(true),
+        concat!(
+            r#"<table class="data"><caption>Heights &amp; ranges</caption>"#,
+            r#"<thead><tr><th>Name</th><th>Height</th><th>Height</th></tr>"#,
+            r#"<tr><th>Name</th><th>m</th><th>ft</th></tr></thead>"#,
+            r#"<tbody><tr><td title="a &quot;peak&quot;"><b>Everest</b></td><td>8848</td><td>29029</td></tr></tbody>"#,
+            r#"</table>"#
+        )
+    );
+
+    // round trip of the extractor: HTML -> Table -> HTML -> Table
+    for table in get_tables("table_span.html", false, false, false)? {
+        for grid in [false, true] {
+            let spanned_table = table.span()?;
+            let html = spanned_table.to_html(grid);
+            let tables = extract_tables(&html, None)?;
+            assert_eq!(tables.len(), 1);
+            assert_eq!(tables[0].span()?.to_list(), spanned_table.to_list());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_to_markdown() -> Result<()> {
+    let html = r#"<table>
+            <tr><th rowspan="2">Name</th><th colspan="2">Height</th></tr>
+            <tr><th>m</th><th>ft</th></tr>
+            <tr><td><a href="https://example.org/everest">Mount Everest</a></td><td><b>8848</b></td><td>29029</td></tr>
+            <tr><td>K2 | Chhogori_x</td><td>8611<br>(est.)</td><td>28251</td></tr>
+        </table>"#;
+    let table = extract_tables(html, None)?[0].span()?;
+
+    assert_eq!(
+        table.to_markdown(CellFormat::Markdown, None),
+        concat!(
+            "| Name | Height / m | Height / ft |\n",
+            "| --- | --- | --- |\n",
+            "| [Mount Everest](https://example.org/everest) | **8848** | 29029 |\n",
+            "| K2 \\| Chhogori\\_x | 8611<br>(est.) | 28251 |\n",
+        )
+    );
+    assert_eq!(
+        table.to_text_grid(CellFormat::Text, Some(8)),
+        concat!(
+            "+----------+----------+----------+\n",
+            "| Name     | Height … | Height … |\n",
+            "+==========+==========+==========+\n",
+            "| Mount E… | 8848     | 29029    |\n",
+            "| K2 | Ch… | 8611 (e… | 28251    |\n",
+            "+----------+----------+----------+\n",
+        )
+    );
+
+    // the header rows are detected when the table has no header cells
+    let html = r#"<table>
+            <tr><td>peak_name</td><td>m</td></tr>
+            <tr><td>Everest</td><td>8848</td></tr>
+        </table>"#;
+    let table = extract_tables(html, None)?[0].span()?;
+    assert_eq!(
+        table.to_markdown(CellFormat::Markdown, None),
+        "| peak\\_name | m |\n| --- | --- |\n| Everest | 8848 |\n"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::get_tables;
+use anyhow::Result;
+use rsoup::{
+    error::RSoupError,
+    models::table::{TableFormat, TableReader, TableWriter},
+};
+
+#[test]
+fn test_write_and_read_tables() -> Result<()> {
+    let tables = get_tables(
+        "wikipedia/2016_Nova_Scotia_municipal_elections.html",
+        true,
+        true,
+        true,
+    )?;
+    assert!(tables.len() > 1);
+
+    for format in [TableFormat::JsonLines, TableFormat::Postcard] {
+        let mut writer = TableWriter::new(Vec::new(), format);
+        for table in &tables {
+            writer.write(table)?;
+        }
+        let bytes = writer.into_inner()?;
+
+        let read_tables =
+            TableReader::new(bytes.as_slice(), format).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(read_tables.len(), tables.len());
+        for (read_table, table) in read_tables.iter().zip(&tables) {
+            assert_eq!(
+                serde_json::to_value(read_table)?,
+                serde_json::to_value(table)?
+            );
+        }
+
+        // a file cut in the middle of a table is an error, not the end of the file
+        let mut reader = TableReader::new(&bytes[..bytes.len() - 3], format);
+        for _ in 0..tables.len() - 1 {
+            assert!(reader.next().unwrap().is_ok());
+        }
+        assert!(reader.next().unwrap().is_err());
+    }
+
+    // blank lines are skipped in newline-delimited JSON
+    let text = format!("{}\n\n{}\n", tables[0].to_json()?, tables[1].to_json()?);
+    let mut reader = TableReader::new(text.as_bytes(), TableFormat::JsonLines);
+    assert_eq!(reader.next().unwrap()?.id, tables[0].id);
+    assert_eq!(reader.next().unwrap()?.id, tables[1].id);
+    assert!(reader.next().is_none());
+
+    // the length of a record is not trusted: a bogus length is a truncated record and does
+    // not allocate a buffer of that length
+    let mut bytes = u64::MAX.to_le_bytes().to_vec();
+    bytes.extend(tables[0].to_bytes()?);
+    let mut reader = TableReader::new(bytes.as_slice(), TableFormat::Postcard);
+    assert!(matches!(reader.next(), Some(Err(RSoupError::IOError(_)))));
+
+    let mut reader = TableReader::new("{\"id\": 1}\n".as_bytes(), TableFormat::JsonLines);
+    assert!(matches!(
+        reader.next(),
+        Some(Err(RSoupError::SerdeJsonError(_)))
+    ));
+
+    Ok(())
+}
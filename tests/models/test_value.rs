@@ -0,0 +1,88 @@
+use crate::extract_tables;
+use anyhow::Result;
+use rsoup::models::table::{Date, ValueParser, ValueType};
+
+#[test]
+fn test_parse_value() {
+    let en = ValueParser::from_locale("en").unwrap();
+    let de = ValueParser::from_locale("de").unwrap();
+    let fr = ValueParser::from_locale("fr_FR").unwrap();
+
+    let value = en.parse("1,234.5");
+    assert_eq!(value.value_type, ValueType::Number);
+    assert_eq!(value.number, Some(1234.5));
+    assert_eq!(de.parse("1.234,5").number, Some(1234.5));
+    assert_eq!(fr.parse("1\u{a0}234,5").number, Some(1234.5));
+    assert_eq!(en.parse("\u{2212}42").number, Some(-42.0));
+    // not a valid grouping of thousands
+    assert_eq!(en.parse("1,2345").value_type, ValueType::Text);
+
+    let value = en.parse("12 %");
+    assert_eq!(value.value_type, ValueType::Percentage);
+    assert_eq!(value.number, Some(12.0));
+    assert_eq!(value.unit.as_deref(), Some("%"));
+
+    let value = en.parse("8,848 m (29,029 ft)");
+    assert_eq!(value.value_type, ValueType::Quantity);
+    assert_eq!(value.number, Some(8848.0));
+    assert_eq!(value.unit.as_deref(), Some("m"));
+    let value = en.parse("$1,500");
+    assert_eq!(
+        (value.value_type, value.number, value.unit.as_deref()),
+        (ValueType::Quantity, Some(1500.0), Some("$"))
+    );
+    let value = en.parse("25 km/h");
+    assert_eq!(
+        (value.value_type, value.number, value.unit.as_deref()),
+        (ValueType::Quantity, Some(25.0), Some("km/h"))
+    );
+    assert_eq!(de.parse("12,50 €").unit.as_deref(), Some("€"));
+    // a number followed by a word is not a quantity
+    assert_eq!(en.parse("2016 Summer Olympics").value_type, ValueType::Text);
+    assert_eq!(en.parse("3 wins").value_type, ValueType::Text);
+    assert_eq!(en.parse("1 m apart").value_type, ValueType::Text);
+
+    let date = Date::new(2016, 3, Some(3));
+    assert_eq!(en.parse("3 March 2016").date, date);
+    assert_eq!(en.parse("March 3, 2016").date, date);
+    assert_eq!(en.parse("2016-03-03").date, date);
+    assert_eq!(en.parse("March 2016").date, Date::new(2016, 3, None));
+    assert_eq!(en.parse("12/31/2016").date, Date::new(2016, 12, Some(31)));
+    assert_eq!(de.parse("31.12.2016").date, Date::new(2016, 12, Some(31)));
+    assert_eq!(en.parse("30 February 2016").value_type, ValueType::Text);
+
+    let value = en.parse("1,234[a][12] ");
+    assert_eq!(value.number, Some(1234.0));
+    assert_eq!(value.text, "1,234");
+    assert_eq!(value.footnotes, vec!["a", "12"]);
+    assert_eq!(en.parse("  ").value_type, ValueType::Empty);
+    assert_eq!(en.parse("Mount Everest").value_type, ValueType::Text);
+
+    assert!(ValueParser::from_locale("xx").is_err());
+}
+
+#[test]
+fn test_column_types() -> Result<()> {
+    let html = r#"<table>
+            <tr><th>Mountain</th><th>Height</th><th>Prominence</th><th>First ascent</th></tr>
+            <tr><td>Everest</td><td>8,848 m</td><td>8848</td><td>29 May 1953</td></tr>
+            <tr><td>K2</td><td>8,611 m[a]</td><td>4,017</td><td>31 July 1954</td></tr>
+            <tr><td>Kangchenjunga</td><td>8,586 m</td><td></td><td>unknown</td></tr>
+        </table>"#;
+    let tables = extract_tables(html, None)?;
+    let parser = ValueParser::default();
+
+    assert_eq!(
+        tables[0].column_types(&parser),
+        vec![
+            ValueType::Text,
+            ValueType::Quantity,
+            ValueType::Number,
+            ValueType::Date
+        ]
+    );
+    let values = tables[0].parse_values(&parser);
+    assert_eq!(values[0][1].value_type, ValueType::Text);
+    assert_eq!(values[2][1].footnotes, vec!["a"]);
+    Ok(())
+}